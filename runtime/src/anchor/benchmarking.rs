@@ -0,0 +1,50 @@
+//! Benchmarks for the anchor module.
+
+use super::*;
+use frame_benchmarking::{account, benchmarks};
+use frame_support::traits::Currency;
+use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+
+const SEED: u32 = 0;
+
+benchmarks! {
+    _ { }
+
+    commit_batch {
+        let b in 1 .. MAX_ANCHORS_IN_BATCH as u32;
+
+        let caller: T::AccountId = account("caller", 0, SEED);
+        <pallet_balances::Module<T> as Currency<_>>::make_free_balance_be(
+            &caller,
+            <T as pallet_balances::Trait>::Balance::max_value(),
+        );
+
+        // store every anchor for two days
+        let stored_until_date = <pallet_timestamp::Module<T>>::get()
+            + T::Moment::from((common::MS_PER_DAY * 2) as u32);
+        let proof = <T as frame_system::Trait>::Hashing::hash_of(&0);
+
+        // pre-commit every anchor, so that each commit checks the pre-commit and its proof
+        let mut commits = Vec::with_capacity(b as usize);
+        for i in 0..b {
+            let anchor_id_preimage = <T as frame_system::Trait>::Hashing::hash_of(&i);
+            let anchor_id = anchor_id_preimage.using_encoded(<T as frame_system::Trait>::Hashing::hash);
+            let signing_root = <T as frame_system::Trait>::Hashing::hash_of(&(i, 1));
+            let mut doc_root_preimage = signing_root.as_ref().to_vec();
+            doc_root_preimage.extend_from_slice(proof.as_ref());
+            let doc_root = <T as frame_system::Trait>::Hashing::hash(&doc_root_preimage);
+
+            Module::<T>::pre_commit(RawOrigin::Signed(caller.clone()).into(), anchor_id, signing_root)?;
+            commits.push(AnchorCommit {
+                anchor_id_preimage,
+                doc_root,
+                proof,
+                stored_until_date,
+            });
+        }
+    }: commit_batch(RawOrigin::Signed(caller), commits, true)
+    verify {
+        assert_eq!(LatestAnchorIndex::get(), b as u64);
+    }
+}
@@ -4,37 +4,80 @@
 //! For a more formally detailed explanation refer section 3.4 of
 //! [Centrifuge Protocol Paper](https://staticw.centrifuge.io/assets/centrifuge_os_protocol_paper.pdf)
 
-use crate::{common, fees};
+use crate::{common, constants::currency, fees};
 use codec::{Decode, Encode};
 use frame_support::{
-    decl_module, decl_storage,
+    decl_error, decl_event, decl_module, decl_storage,
     dispatch::{DispatchError, DispatchResult},
     ensure,
-    storage::{child},
-    weights::{DispatchClass},
+    storage::{
+        child::{self, ChildInfo},
+        StorageMap,
+    },
+    traits::{Currency, EnsureOrigin, ExistenceRequirement, Get},
+    weights::{DispatchClass, Weight},
+    RuntimeDebug,
+};
+use frame_system::{ensure_root, ensure_signed};
+use sp_runtime::{
+    traits::{AccountIdConversion, CheckedAdd, CheckedSub, Hash, One, Saturating, Zero},
+    ModuleId, Perbill, SaturatedConversion,
 };
-use frame_system::ensure_signed;
-use sp_runtime::traits::Hash;
 use sp_std::{convert::TryInto, vec::Vec};
+use sp_trie::{read_trie_value, Layout, StorageProof};
 
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 
-/// Expiration duration in blocks of a pre-commit
-/// This is the maximum expected time for document consensus to take place between a pre-commit of an anchor and a
-/// commit to be received for the pre-committed anchor. Currently we expect to provide around 80 mins for this.
-/// Since our current block time as per chain_spec.rs is 6s, we set this to 80 * 60 secs / 6 secs/block = 800 blocks.
-const PRE_COMMIT_EXPIRATION_DURATION_BLOCKS: u64 = 800;
+/// Maximum number of anchors that can be committed in a single `commit_batch` call.
+const MAX_ANCHORS_IN_BATCH: usize = 100;
+
+/// Maximum number of anchors returned by a single call of the anchor listing functions.
+pub const MAX_ANCHORS_PER_PAGE: u32 = 1000;
+
+/// Share of the maximum block weight that can be spent on evicting expired pre-commits and
+/// anchors in `on_initialize`.
+const EVICTION_BLOCK_WEIGHT_RATIO: Perbill = Perbill::from_percent(10);
+
+/// Weight of computing and storing the root of the anchor child trie of a single day before it
+/// is evicted, on top of its storage accesses.
+const EVICT_ANCHOR_CHILD_TRIE_WEIGHT: Weight = 50_000_000;
+
+/// Weight of removing a single anchor from the child trie of its eviction day, on top of its
+/// storage accesses.
+const EVICT_ANCHOR_WEIGHT: Weight = 1_000_000;
+
+/// Weight of checking an evicted anchor proof, for each of its trie nodes and for each byte of
+/// them, which are hashed while the proof is read.
+const EVICTED_ANCHOR_PROOF_WEIGHT_PER_NODE: Weight = 2_000_000;
+const EVICTED_ANCHOR_PROOF_WEIGHT_PER_BYTE: Weight = 5_000;
+
+/// Id of the anchor module, used to derive the account of the eviction bounty pool.
+const MODULE_ID: ModuleId = ModuleId(*b"an/evict");
 
-/// MUST be higher than 1 to assure that pre-commits are around during their validity time frame
-/// The higher the number, the more pre-commits will be collected in a single eviction bucket
-const PRE_COMMIT_EVICTION_BUCKET_MULTIPLIER: u64 = 5;
+/// Bounty paid out of the eviction bounty pool for every pre-commit removed by `evict_pre_commits`.
+const PRE_COMMIT_EVICTION_BOUNTY: u128 = 1 * currency::MICRO_CFG;
 
-/// Determines how many loop iterations are allowed to run at a time inside the runtime.
-const MAX_LOOP_IN_TX: u64 = 500;
+/// Bounty paid out of the eviction bounty pool for every anchor child trie removed by
+/// `evict_anchors`.
+const ANCHOR_CHILD_TRIE_EVICTION_BOUNTY: u128 = 10 * currency::MICRO_CFG;
 
-/// date 3000-01-01 -> 376200 days from unix epoch
-const STORAGE_MAX_DAYS: u32 = 376200;
+/// Weights of the extrinsics of this module, as generated from the benchmarks.
+pub trait WeightInfo {
+    /// Weight of a batch of `b` commits of pre-committed anchors, see the `commit_batch`
+    /// benchmark.
+    fn commit_batch(b: u32) -> Weight;
+}
+
+/// Estimated weights, to be used until weights are generated from the benchmarks: a base
+/// weight and, for every anchor, the reads of its indexes and pre-commit, the writes of its
+/// child trie entry and indexes and the check of the pre-commit proof.
+impl WeightInfo for () {
+    fn commit_batch(b: u32) -> Weight {
+        (190_000_000 as Weight)
+            .saturating_add((520_000_000 as Weight).saturating_mul(b as Weight))
+    }
+}
 
 /// Child trie prefix
 const ANCHOR_PREFIX: &[u8; 6] = b"anchor";
@@ -57,10 +100,79 @@ pub struct AnchorData<Hash, BlockNumber> {
     anchored_block: BlockNumber,
 }
 
+/// A committed anchor together with the date it will be evicted on, as returned by the anchor
+/// listing functions.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+pub struct AnchorWithEvictDate<Hash, BlockNumber> {
+    pub anchor: AnchorData<Hash, BlockNumber>,
+    /// Eviction date of the anchor in days since unix epoch.
+    pub evict_date: u32,
+}
+
+/// Counters of the anchor index, see `LatestAnchorIndex` and `LatestEvictedAnchorIndex`.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+pub struct AnchorCounts {
+    /// Number of anchors committed so far, which is also the highest anchor index.
+    pub latest_index: u64,
+    /// Highest anchor index whose anchor has been evicted.
+    pub latest_evicted_index: u64,
+}
+
+/// A single anchor commit as part of a `commit_batch` call. The fields have the same meaning as
+/// the arguments of `commit`.
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+pub struct AnchorCommit<Hash, Moment> {
+    pub anchor_id_preimage: Hash,
+    pub doc_root: Hash,
+    pub proof: Hash,
+    pub stored_until_date: Moment,
+}
+
+/// Proof of an anchor that was evicted together with the child trie of its eviction day. See
+/// `verify_evicted_anchor` for how it is checked.
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+pub struct EvictedAnchorProof {
+    /// Eviction day of the anchor in days since unix epoch.
+    pub day: u32,
+    /// Trie nodes of the child trie of `day` that lead to the anchor.
+    pub proof: Vec<Vec<u8>>,
+}
+
 /// The module's configuration trait.
 pub trait Trait:
     frame_system::Trait + pallet_timestamp::Trait + fees::Trait + pallet_balances::Trait
 {
+    /// The overarching event type.
+    type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+    /// Weights of the extrinsics of this module.
+    type WeightInfo: WeightInfo;
+
+    /// Number of blocks a pre-commit is valid for, unless overridden through
+    /// `set_pre_commit_expiration_duration`. This is the maximum expected time for document
+    /// consensus to take place between a pre-commit of an anchor and its commit.
+    type PreCommitExpirationDurationBlocks: Get<Self::BlockNumber>;
+
+    /// Number of `PreCommitExpirationDurationBlocks` covered by a single pre-commit eviction
+    /// bucket. The higher the number, the more pre-commits are collected in a bucket.
+    type PreCommitEvictionBucketMultiplier: Get<u32>;
+
+    /// Number of loop iterations allowed to run at a time inside the runtime.
+    type MaxLoopInTx: Get<u64>;
+
+    /// Maximum date, in days since unix epoch, until which an anchor can be stored.
+    type StorageMaxDays: Get<u32>;
+
+    /// Maximum number of trie nodes of a proof of an evicted anchor.
+    type MaxEvictedAnchorProofNodes: Get<u32>;
+
+    /// Maximum size in bytes of all trie nodes of a proof of an evicted anchor together.
+    type MaxEvictedAnchorProofSize: Get<u32>;
+
+    /// Required origin for overriding the pre-commit expiration duration.
+    type AdminOrigin: EnsureOrigin<Self::Origin>;
 }
 
 decl_storage! {
@@ -74,6 +186,10 @@ decl_storage! {
         PreCommitEvictionBuckets get(fn get_pre_commit_in_evict_bucket_by_index): map hasher(opaque_blake2_256) (T::BlockNumber, u64) => T::Hash;
         PreCommitEvictionBucketIndex get(fn get_pre_commits_count_in_evict_bucket): map hasher(opaque_blake2_256) T::BlockNumber => u64;
 
+        /// Position of a pre-commit in the eviction buckets given its anchor id, so that it can be
+        /// removed from its bucket when it is cancelled.
+        PreCommitEvictionBucketPositions get(fn get_pre_commit_eviction_bucket_position): map hasher(opaque_blake2_256) T::Hash => Option<(T::BlockNumber, u64)>;
+
         /// Index to find the eviction date given an anchor id
         AnchorEvictDates get(fn get_anchor_evict_date): map hasher(opaque_blake2_256) T::Hash => u32;
 
@@ -98,12 +214,122 @@ decl_storage! {
         /// evicted anchor.
         EvictedAnchorRoots get(fn get_evicted_anchor_root_by_day): map hasher(opaque_blake2_256) u32 => Vec<u8>;
 
+        /// Block number in which the eviction of the child trie of anchors for a given day started.
+        /// The state of its parent block is the last one that contains the whole child trie, so
+        /// proofs for evicted anchors are read from there.
+        EvictedAnchorBlocks get(fn get_evicted_anchor_block_by_day): map hasher(opaque_blake2_256) u32 => Option<T::BlockNumber>;
+
+        /// Next pre-commit eviction bucket to be evicted in `on_initialize`. Buckets are evicted
+        /// in order, the cursor only moves on once a bucket is empty.
+        NextPreCommitEvictionBucket get(fn get_next_pre_commit_eviction_bucket): T::BlockNumber;
+
+        /// Pre-commit expiration duration set through governance. Takes precedence over
+        /// `PreCommitExpirationDurationBlocks` of the module configuration.
+        PreCommitExpirationDurationOverride get(fn get_pre_commit_expiration_duration_override): Option<T::BlockNumber>;
+
         Version: u64;
     }
 }
 
+decl_event!(
+    pub enum Event<T>
+    where
+        <T as frame_system::Trait>::Hash,
+        <T as frame_system::Trait>::AccountId,
+        <T as frame_system::Trait>::BlockNumber,
+        <T as pallet_balances::Trait>::Balance,
+    {
+        /// An anchor id was pre-committed. [anchor_id, who, expiration_block]
+        PreCommitted(Hash, AccountId, BlockNumber),
+        /// A pre-commit was cancelled by its pre-committer. [anchor_id, who]
+        PreCommitCancelled(Hash, AccountId),
+        /// A document root was anchored. [anchor_id, doc_root, stored_until_day]
+        AnchorCommitted(Hash, Hash, u32),
+        /// Expired pre-commits were evicted from an eviction bucket. [bucket, count]
+        PreCommitsEvicted(BlockNumber, u64),
+        /// Anchor child tries of the given days were evicted. [from_day, to_day, tries_removed]
+        AnchorsEvicted(u32, u32, u32),
+        /// The storage period of an anchor was extended. [anchor_id, old_stored_until_day, new_stored_until_day]
+        AnchorExtended(Hash, u32, u32),
+        /// A bounty for evicting pre-commits or anchors was paid from the eviction bounty pool. [who, amount]
+        EvictionBountyPaid(AccountId, Balance),
+        /// The pre-commit expiration duration override was changed, `None` if it was removed. [blocks]
+        PreCommitExpirationDurationChanged(Option<BlockNumber>),
+    }
+);
+
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// An anchor with the given id already exists.
+        AnchorAlreadyExists,
+        /// A valid pre-commit already exists for the given anchor id.
+        PreCommitAlreadyExists,
+        /// The pre-commit for the anchor id is owned by a different account.
+        NotOwnerOfPreCommit,
+        /// There is no pre-commit for the anchor id.
+        PreCommitNotFound,
+        /// Hash of the pre-committed signing root and the proof does not match the document root.
+        InvalidPreCommitProof,
+        /// The stored until date is not at least a day later than the current date.
+        EvictionDateTooEarly,
+        /// The stored until date is more than the maximum allowed from now.
+        EvictionDateTooBig,
+        /// A timestamp could not be converted to a u64.
+        TimestampConversionFailed,
+        /// The pre-commit expiration block number does not fit into a u32.
+        PreCommitExpirationBlockTooBig,
+        /// Pre-commits of the given bucket can not be evicted before the bucket has expired.
+        EvictionBucketNotExpired,
+        /// No fee is configured for anchoring, so the state rent can not be calculated.
+        AnchorFeeNotFound,
+        /// Anchor data stored in the child trie could not be decoded.
+        AnchorDataCorrupted,
+        /// A `commit_batch` call did not contain any commit.
+        EmptyBatch,
+        /// A `commit_batch` call contained more commits than allowed.
+        BatchTooLarge,
+        /// None of the commits in a best effort `commit_batch` call were valid.
+        NoValidCommitInBatch,
+        /// No child trie root is stored for the given eviction day.
+        EvictedAnchorRootNotFound,
+        /// The proof does not contain the anchor under the child trie root of the eviction day.
+        InvalidEvictedAnchorProof,
+        /// The proof of an evicted anchor has more trie nodes or bytes than allowed.
+        EvictedAnchorProofTooLarge,
+        /// The evicted anchor was committed with a different document root.
+        EvictedAnchorDocRootMismatch,
+        /// No anchor with the given id is stored.
+        AnchorNotFound,
+        /// The new stored until date is not later than the current one of the anchor.
+        EvictionDateNotExtended,
+        /// The pre-commit expiration duration must be at least one block.
+        InvalidPreCommitExpirationDuration,
+    }
+}
+
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
+        fn deposit_event() = default;
+
+        /// Number of blocks a pre-commit is valid for, unless overridden through governance.
+        const PreCommitExpirationDurationBlocks: T::BlockNumber = T::PreCommitExpirationDurationBlocks::get();
+
+        /// Number of pre-commit expiration durations covered by a pre-commit eviction bucket.
+        const PreCommitEvictionBucketMultiplier: u32 = T::PreCommitEvictionBucketMultiplier::get();
+
+        /// Number of loop iterations allowed to run at a time inside the runtime.
+        const MaxLoopInTx: u64 = T::MaxLoopInTx::get();
+
+        /// Maximum date, in days since unix epoch, until which an anchor can be stored.
+        const StorageMaxDays: u32 = T::StorageMaxDays::get();
+
+        /// Evicts expired pre-commits and anchors within a share of the block weight, so that
+        /// state does not build up when nobody calls `evict_pre_commits` or `evict_anchors`.
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            Self::evict_expired(now)
+        }
 
         /// Obtains an exclusive lock to make the next update to a certain document version
         /// identified by `anchor_id` on Centrifuge p2p network for a number of blocks given
@@ -124,11 +350,11 @@ decl_module! {
         #[weight = 193_000_000]
         pub fn pre_commit(origin, anchor_id: T::Hash, signing_root: T::Hash) -> DispatchResult {
             let who = ensure_signed(origin)?;
-            ensure!(Self::get_anchor_by_id(anchor_id).is_none(), "Anchor already exists");
-            ensure!(!Self::has_valid_pre_commit(anchor_id), "A valid pre-commit already exists");
+            ensure!(Self::get_anchor_by_id(anchor_id)?.is_none(), Error::<T>::AnchorAlreadyExists);
+            ensure!(!Self::has_valid_pre_commit(anchor_id), Error::<T>::PreCommitAlreadyExists);
 
             let expiration_block = <frame_system::Module<T>>::block_number()  +
-                T::BlockNumber::from(Self::pre_commit_expiration_duration_blocks() as u32);
+                Self::pre_commit_expiration_duration_blocks();
             <PreCommits<T>>::insert(anchor_id, PreCommitData {
                 signing_root: signing_root,
                 identity: who.clone(),
@@ -137,6 +363,28 @@ decl_module! {
 
             Self::put_pre_commit_into_eviction_bucket(anchor_id, expiration_block)?;
 
+            Self::deposit_event(RawEvent::PreCommitted(anchor_id, who, expiration_block));
+
+            Ok(())
+        }
+
+        /// Releases the lock on `anchor_id` obtained through `pre_commit` before it expires, e.g.
+        /// when the document consensus failed. Only the pre-committer may cancel a pre-commit.
+        ///
+        /// # <weight>
+        /// Independent of the inputs. Removes the pre-commit and swaps the last pre-commit of its
+        /// eviction bucket into its position.
+        /// # </weight>
+        #[weight = 193_000_000]
+        pub fn cancel_pre_commit(origin, anchor_id: T::Hash) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(<PreCommits<T>>::contains_key(anchor_id), Error::<T>::PreCommitNotFound);
+            ensure!(<PreCommits<T>>::get(anchor_id).identity == who, Error::<T>::NotOwnerOfPreCommit);
+
+            <PreCommits<T>>::remove(anchor_id);
+            Self::remove_pre_commit_from_eviction_bucket(anchor_id);
+
+            Self::deposit_event(RawEvent::PreCommitCancelled(anchor_id, who));
             Ok(())
         }
 
@@ -158,116 +406,183 @@ decl_module! {
         #[weight = 190_000_000]
         pub fn commit(origin, anchor_id_preimage: T::Hash, doc_root: T::Hash, proof: T::Hash, stored_until_date: T::Moment) -> DispatchResult {
             let who = ensure_signed(origin)?;
-            ensure!(<pallet_timestamp::Module<T>>::get() + T::Moment::from(common::MS_PER_DAY.try_into().unwrap()) < stored_until_date,
-                "Stored until date must be at least a day later than the current date");
-
-            // validate the eviction date
-            let eviction_date_u64 = TryInto::<u64>::try_into(stored_until_date)
-                .map_err(|_e| "Can not convert eviction date to u64")
-                .unwrap();
-            let stored_until_date_from_epoch = common::get_days_since_epoch(eviction_date_u64);
-            ensure!(Self::anchor_storage_max_days_from_now() >= stored_until_date_from_epoch, "The provided stored until date is more than the maximum allowed from now");
-
-            let anchor_id = (anchor_id_preimage)
-                .using_encoded(<T as frame_system::Trait>::Hashing::hash);
-            ensure!(Self::get_anchor_by_id(anchor_id).is_none(), "Anchor already exists");
-
-            if Self::has_valid_pre_commit(anchor_id) {
-                ensure!(<PreCommits<T>>::get(anchor_id).identity == who, "Pre-commit owned by someone else");
-                ensure!(Self::has_valid_pre_commit_proof(anchor_id, doc_root, proof), "Pre-commit proof not valid");
+            let (anchor_id, stored_until_date_from_epoch) =
+                Self::validate_commit(&who, anchor_id_preimage, doc_root, proof, stored_until_date)?;
+
+            // pay state rent to block author
+            let fee = Self::state_rent(stored_until_date_from_epoch)?;
+            Self::pay_state_rent(who, fee)?;
+
+            Self::store_anchor(anchor_id, doc_root, stored_until_date_from_epoch);
+            Self::deposit_event(RawEvent::AnchorCommitted(anchor_id, doc_root, stored_until_date_from_epoch));
+
+            Ok(())
+        }
+
+        /// Commits a batch of anchors in a single extrinsic. Every entry is validated the same way
+        /// as in `commit`, including the pre-commit ownership and proof checks. The state rent of
+        /// all committed anchors is summed up and charged once.
+        ///
+        /// If `all_or_nothing` is set, the first invalid entry fails the whole call and nothing is
+        /// committed. Otherwise invalid entries are skipped and the remaining ones are committed.
+        /// The call fails if none of the entries are valid.
+        ///
+        /// # <weight>
+        /// Grows linearly with the number of commits in the batch, which is capped by
+        /// `MAX_ANCHORS_IN_BATCH`. Each commit reads the anchor indexes and the pre-commit,
+        /// checks the pre-commit proof and writes the child trie entry plus its indexes.
+        /// # </weight>
+        #[weight = T::WeightInfo::commit_batch(commits.len() as u32)]
+        pub fn commit_batch(origin, commits: Vec<AnchorCommit<T::Hash, T::Moment>>, all_or_nothing: bool) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(!commits.is_empty(), Error::<T>::EmptyBatch);
+            ensure!(commits.len() <= MAX_ANCHORS_IN_BATCH, Error::<T>::BatchTooLarge);
+
+            let mut valid_commits: Vec<(T::Hash, T::Hash, u32)> = Vec::with_capacity(commits.len());
+            for c in commits.into_iter() {
+                let result = Self::validate_commit(&who, c.anchor_id_preimage, c.doc_root, c.proof, c.stored_until_date)
+                    .and_then(|(anchor_id, stored_until_date_from_epoch)| {
+                        // the same anchor can not be committed twice within a batch
+                        ensure!(!valid_commits.iter().any(|(id, _, _)| *id == anchor_id), Error::<T>::AnchorAlreadyExists);
+                        Ok((anchor_id, c.doc_root, stored_until_date_from_epoch))
+                    });
+
+                match result {
+                    Ok(valid_commit) => valid_commits.push(valid_commit),
+                    Err(e) if all_or_nothing => return Err(e),
+                    Err(_) => continue,
+                }
             }
+            ensure!(!valid_commits.is_empty(), Error::<T>::NoValidCommitInBatch);
 
-             // pay the state rent
-            let today_in_days_from_epoch = TryInto::<u64>::try_into(<pallet_timestamp::Module<T>>::get())
-                .map(common::get_days_since_epoch)
-                .map_err(|_e| "Can not convert timestamp to u64")
-                .unwrap();
+            // pay the state rent of the whole batch at once to block author
+            let fee = valid_commits.iter()
+                .try_fold(Zero::zero(), |total: <T as pallet_balances::Trait>::Balance, (_, _, stored_until_date_from_epoch)| {
+                    Self::state_rent(*stored_until_date_from_epoch).map(|rent| total.saturating_add(rent))
+                })?;
+            Self::pay_state_rent(who, fee)?;
 
-            // we use the fee config setup on genesis for anchoring to calculate the state rent
-            let fee = <fees::Module<T>>::price_of(Self::fee_key()).unwrap() *
-                <T as pallet_balances::Trait>::Balance::from(stored_until_date_from_epoch - today_in_days_from_epoch);
+            valid_commits.into_iter()
+                .for_each(|(anchor_id, doc_root, stored_until_date_from_epoch)| {
+                    Self::store_anchor(anchor_id, doc_root, stored_until_date_from_epoch);
+                    Self::deposit_event(RawEvent::AnchorCommitted(anchor_id, doc_root, stored_until_date_from_epoch));
+                });
 
-            // pay state rent to block author
-            <fees::Module<T>>::pay_fee_to_author(who, fee)?;
+            Ok(())
+        }
 
-            let block_num = <frame_system::Module<T>>::block_number();
-            let anchor_data = AnchorData {
-                id: anchor_id,
-                doc_root: doc_root,
-                anchored_block: block_num
-            };
+        /// Extends the storage period of an existing anchor to `new_stored_until_date`, which must
+        /// be more than a day from now, the same as for `commit`. The anchor data is moved to the
+        /// child trie of the new eviction date, so it is evicted only after that date. The calling
+        /// account is charged the state rent of the extra days only.
+        ///
+        /// # <weight>
+        /// Same as `commit`, the anchor is read from and written to child tries and the state rent
+        /// is calculated for the extra days.
+        /// # </weight>
+        #[weight = 190_000_000]
+        pub fn extend_anchor(origin, anchor_id: T::Hash, new_stored_until_date: T::Moment) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let anchor_data = Self::get_anchor_by_id(anchor_id)?.ok_or(Error::<T>::AnchorNotFound)?;
+
+            let stored_until_date_from_epoch = <AnchorEvictDates<T>>::get(anchor_id);
+            let new_stored_until_date_from_epoch = Self::validate_stored_until_date(new_stored_until_date)?;
+            ensure!(new_stored_until_date_from_epoch > stored_until_date_from_epoch,
+                Error::<T>::EvictionDateNotExtended);
+
+            // pay state rent of the extra days to block author
+            let fee = Self::rent_for_days(new_stored_until_date_from_epoch - stored_until_date_from_epoch)?;
+            Self::pay_state_rent(who, fee)?;
 
-            let prefixed_key = Self::anchor_storage_key(&stored_until_date_from_epoch.encode());
-            Self::store_anchor(anchor_id, &prefixed_key, stored_until_date_from_epoch, &anchor_data.encode());
+            // move the anchor to the child trie of its new eviction date
+            child::kill(&Self::anchor_child_info(stored_until_date_from_epoch), anchor_id.as_ref());
+            child::put_raw(
+                &Self::anchor_child_info(new_stored_until_date_from_epoch),
+                anchor_id.as_ref(),
+                &anchor_data.encode(),
+            );
+            <AnchorEvictDates<T>>::insert(&anchor_id, &new_stored_until_date_from_epoch);
 
+            Self::deposit_event(RawEvent::AnchorExtended(
+                anchor_id, stored_until_date_from_epoch, new_stored_until_date_from_epoch));
+
+            Ok(())
+        }
+
+        /// Overrides the number of blocks a pre-commit is valid for. `None` removes the override, so
+        /// that `PreCommitExpirationDurationBlocks` of the module configuration applies again.
+        /// Only affects pre-commits made after the change.
+        ///
+        /// # <weight>
+        /// - Independent of the arguments.
+        /// - A single write.
+        /// # </weight>
+        #[weight = (195_000_000, DispatchClass::Operational)]
+        pub fn set_pre_commit_expiration_duration(origin, blocks: Option<T::BlockNumber>) -> DispatchResult {
+            T::AdminOrigin::try_origin(origin)
+                .map(|_| ())
+                .or_else(ensure_root)?;
+            ensure!(blocks.map_or(true, |blocks| !blocks.is_zero()),
+                Error::<T>::InvalidPreCommitExpirationDuration);
+
+            match blocks {
+                Some(blocks) => <PreCommitExpirationDurationOverride<T>>::put(blocks),
+                None => <PreCommitExpirationDurationOverride<T>>::kill(),
+            }
+
+            Self::deposit_event(RawEvent::PreCommitExpirationDurationChanged(blocks));
             Ok(())
         }
 
         /// Initiates eviction of pre-commits that has expired given that the current block number
         /// has progressed past the block number provided in `evict_bucket`. `evict_bucket` is also
         /// the index to find the pre-commits stored in storage to be evicted when the
-        /// `evict_bucket` number of blocks has expired.
+        /// `evict_bucket` number of blocks has expired. The caller is paid a bounty for every
+        /// evicted pre-commit out of the eviction bounty pool.
         ///
         /// # <weight>
         /// - discourage DoS
         /// # </weight>
         #[weight = (192_000_000, DispatchClass::Operational)]
         pub fn evict_pre_commits(origin, evict_bucket: T::BlockNumber) -> DispatchResult {
-            ensure_signed(origin)?;
+            let who = ensure_signed(origin)?;
             ensure!(<frame_system::Module<T>>::block_number() >= evict_bucket,
-                "eviction only possible for bucket expiring < current block height");
-
-            let pre_commits_count = Self::get_pre_commits_count_in_evict_bucket(evict_bucket);
-            for idx in (0..pre_commits_count).rev() {
-                if pre_commits_count - idx > MAX_LOOP_IN_TX {
-                    break;
-                }
+                Error::<T>::EvictionBucketNotExpired);
 
-                let pre_commit_id =
-                    Self::get_pre_commit_in_evict_bucket_by_index((evict_bucket, idx));
-                <PreCommits<T>>::remove(pre_commit_id);
+            let (_, evicted_count) =
+                Self::remove_pre_commits_in_bucket(evict_bucket, T::MaxLoopInTx::get());
 
-                <PreCommitEvictionBuckets<T>>::remove((evict_bucket, idx));
-
-                // decreases the evict bucket item count or remove index completely if empty
-                if idx == 0 {
-                    <PreCommitEvictionBucketIndex<T>>::remove(evict_bucket);
-                } else {
-                    <PreCommitEvictionBucketIndex<T>>::insert(evict_bucket, idx);
-                }
-            }
-            Ok(())
+            Self::deposit_event(RawEvent::PreCommitsEvicted(evict_bucket, evicted_count));
+            Self::pay_eviction_bounty(&who, PRE_COMMIT_EVICTION_BOUNTY, evicted_count as u128)
         }
 
         /// Initiates eviction of expired anchors. Since anchors are stored on a child trie indexed by
         /// their eviction date, what this function does is to remove those child tries which has
         /// date_represented_by_root < current_date. Additionally it needs to take care of indexes
-        /// created for accessing anchors, eg: to find an anchor given an id.
+        /// created for accessing anchors, eg: to find an anchor given an id. The caller is paid a
+        /// bounty for every evicted child trie out of the eviction bounty pool.
         ///
         /// # <weight>
         /// - discourage DoS
         /// # </weight>
         #[weight = (195_000_000, DispatchClass::Operational)]
         pub fn evict_anchors(origin) -> DispatchResult {
-            ensure_signed(origin)?;
-            let current_timestamp = <pallet_timestamp::Module<T>>::get();
+            let who = ensure_signed(origin)?;
 
             // get the today counting epoch, so that we can remove the corresponding child trie
-            let today_in_days_from_epoch = TryInto::<u64>::try_into(current_timestamp)
-                .map(common::get_days_since_epoch)
-                .map_err(|_e| "Can not convert timestamp to u64")
-                .unwrap();
+            let today_in_days_from_epoch = Self::today_in_days_from_epoch()?;
             let evict_date = LatestEvictedDate::get();
 
             // remove child tries starting from day next to last evicted day
-            let _evicted_trie_count = Self::evict_anchor_child_tries(evict_date + 1, today_in_days_from_epoch);
+            let evicted_trie_count = Self::evict_anchor_child_tries(evict_date + 1, today_in_days_from_epoch);
 
             // store yesterday as the last day of eviction
             let yesterday = today_in_days_from_epoch - 1;
             LatestEvictedDate::put(yesterday);
             let _evicted_anchor_indexes_count = Self::remove_anchor_indexes(yesterday);
 
-            Ok(())
+            Self::deposit_event(RawEvent::AnchorsEvicted(evict_date + 1, yesterday, evicted_trie_count as u32));
+            Self::pay_eviction_bounty(&who, ANCHOR_CHILD_TRIE_EVICTION_BOUNTY, evicted_trie_count as u128)
         }
     }
 }
@@ -297,13 +612,14 @@ impl<T: Trait> Module<T> {
     }
 
     /// How long before we expire a pre-commit
-    fn pre_commit_expiration_duration_blocks() -> u64 {
-        PRE_COMMIT_EXPIRATION_DURATION_BLOCKS
+    fn pre_commit_expiration_duration_blocks() -> T::BlockNumber {
+        Self::get_pre_commit_expiration_duration_override()
+            .unwrap_or_else(T::PreCommitExpirationDurationBlocks::get)
     }
 
     /// Get the maximum days allowed for an anchor to be stored on chain from unix epoch onwards.
     fn anchor_storage_max_days_from_now() -> u32 {
-        STORAGE_MAX_DAYS
+        T::StorageMaxDays::get()
     }
 
     /// Puts the pre-commit (based on anchor_id) into the correct eviction bucket
@@ -323,30 +639,61 @@ impl<T: Trait> Module<T> {
             (evict_after_block.clone(), eviction_bucket_size.clone()),
             anchor_id,
         );
+        <PreCommitEvictionBucketPositions<T>>::insert(
+            anchor_id,
+            (evict_after_block, eviction_bucket_size),
+        );
         eviction_bucket_size += 1;
         <PreCommitEvictionBucketIndex<T>>::insert(evict_after_block, eviction_bucket_size);
         Ok(())
     }
 
+    /// Removes the pre-commit of `anchor_id` from its eviction bucket. The last pre-commit of the
+    /// bucket takes its position, so that the bucket stays contiguous for `evict_pre_commits`.
+    fn remove_pre_commit_from_eviction_bucket(anchor_id: T::Hash) {
+        let (bucket, idx) = match <PreCommitEvictionBucketPositions<T>>::take(anchor_id) {
+            Some(position) => position,
+            // pre-commits made before positions were tracked stay in their bucket until evicted
+            None => return,
+        };
+
+        let last_idx = Self::get_pre_commits_count_in_evict_bucket(bucket).saturating_sub(1);
+        if idx != last_idx {
+            let moved_id = Self::get_pre_commit_in_evict_bucket_by_index((bucket, last_idx));
+            <PreCommitEvictionBuckets<T>>::insert((bucket, idx), moved_id);
+            if Self::get_pre_commit_eviction_bucket_position(moved_id) == Some((bucket, last_idx)) {
+                <PreCommitEvictionBucketPositions<T>>::insert(moved_id, (bucket, idx));
+            }
+        }
+        <PreCommitEvictionBuckets<T>>::remove((bucket, last_idx));
+
+        if last_idx == 0 {
+            <PreCommitEvictionBucketIndex<T>>::remove(bucket);
+        } else {
+            <PreCommitEvictionBucketIndex<T>>::insert(bucket, last_idx);
+        }
+    }
+
+    /// Number of blocks between two pre-commit eviction buckets. It is based on the configured
+    /// pre-commit expiration duration, not on its governance override, so that the buckets do
+    /// not move when the override changes. A pre-commit always expires before its bucket.
+    fn pre_commit_eviction_bucket_size() -> T::BlockNumber {
+        T::PreCommitExpirationDurationBlocks::get()
+            .saturating_mul(T::BlockNumber::from(T::PreCommitEvictionBucketMultiplier::get()))
+            .max(One::one())
+    }
+
     /// Determines the next eviction bucket number based on the given BlockNumber
     /// This can be used to determine which eviction bucket a pre-commit
     /// should be put into for later eviction.
     fn determine_pre_commit_eviction_bucket(
         pre_commit_expiration_block: T::BlockNumber,
     ) -> Result<T::BlockNumber, DispatchError> {
-        let result = TryInto::<u32>::try_into(pre_commit_expiration_block);
-        match result {
-            Ok(u32_expiration_block) => {
-                let expiration_horizon = Self::pre_commit_expiration_duration_blocks() as u32
-                    * PRE_COMMIT_EVICTION_BUCKET_MULTIPLIER as u32;
-                let put_into_bucket = u32_expiration_block
-                    - (u32_expiration_block % expiration_horizon)
-                    + expiration_horizon;
-
-                Ok(T::BlockNumber::from(put_into_bucket))
-            }
-            Err(_e) => Err(DispatchError::Other("pre commit expiration block too big")),
-        }
+        let expiration_horizon = Self::pre_commit_eviction_bucket_size();
+        pre_commit_expiration_block
+            .checked_sub(&(pre_commit_expiration_block % expiration_horizon))
+            .and_then(|block| block.checked_add(&expiration_horizon))
+            .ok_or_else(|| Error::<T>::PreCommitExpirationBlockTooBig.into())
     }
 
     /// Remove child tries starting with `from` day to `until` day returning the
@@ -359,6 +706,7 @@ impl<T: Trait> Module<T> {
             .map(|(day, key)| {
                 if !EvictedAnchorRoots::contains_key(day) {
                     EvictedAnchorRoots::insert(day, child::root(&key));
+                    <EvictedAnchorBlocks<T>>::insert(day, <frame_system::Module<T>>::block_number());
                 }
                 key
             })
@@ -366,13 +714,200 @@ impl<T: Trait> Module<T> {
             .count()
     }
 
+    /// Removes up to `limit` anchors from the child trie of `day`. The root of the child trie is
+    /// stored before the first anchor is removed. Returns the number of removed anchors and
+    /// whether the child trie is empty.
+    fn remove_anchors_of_day(day: u32, limit: u64) -> (u64, bool) {
+        let child_info = Self::anchor_child_info(day);
+        if !EvictedAnchorRoots::contains_key(day) {
+            EvictedAnchorRoots::insert(day, child::root(&child_info));
+            <EvictedAnchorBlocks<T>>::insert(day, <frame_system::Module<T>>::block_number());
+        }
+
+        // continue after the last removed key, which is faster than starting over
+        let mut key = Vec::new();
+        let mut removed_count = 0;
+        while let Some(next_key) = sp_io::default_child_storage::next_key(child_info.storage_key(), &key) {
+            if removed_count == limit {
+                return (removed_count, false);
+            }
+            child::kill(&child_info, &next_key);
+            key = next_key;
+            removed_count += 1;
+        }
+        (removed_count, true)
+    }
+
+    /// Clears up to `limit` entries of the given eviction bucket, starting with the last one.
+    /// Returns the number of cleared bucket entries and the number of pre-commits actually
+    /// removed, which is lower if some of the anchor ids were pre-committed again since.
+    fn remove_pre_commits_in_bucket(evict_bucket: T::BlockNumber, limit: u64) -> (u64, u64) {
+        let pre_commits_count = Self::get_pre_commits_count_in_evict_bucket(evict_bucket);
+        let mut cleared_count: u64 = 0;
+        let mut evicted_count: u64 = 0;
+        for idx in (0..pre_commits_count).rev() {
+            if pre_commits_count - idx > limit {
+                break;
+            }
+
+            let pre_commit_id =
+                Self::get_pre_commit_in_evict_bucket_by_index((evict_bucket, idx));
+            // the anchor id might have been pre-committed again into a later bucket after its
+            // pre-commit in this bucket expired, which must not be evicted yet
+            match Self::get_pre_commit_eviction_bucket_position(pre_commit_id) {
+                Some(position) if position != (evict_bucket, idx) => (),
+                _ => {
+                    <PreCommits<T>>::remove(pre_commit_id);
+                    <PreCommitEvictionBucketPositions<T>>::remove(pre_commit_id);
+                    evicted_count += 1;
+                }
+            }
+
+            <PreCommitEvictionBuckets<T>>::remove((evict_bucket, idx));
+
+            // decreases the evict bucket item count or remove index completely if empty
+            if idx == 0 {
+                <PreCommitEvictionBucketIndex<T>>::remove(evict_bucket);
+            } else {
+                <PreCommitEvictionBucketIndex<T>>::insert(evict_bucket, idx);
+            }
+            cleared_count += 1;
+        }
+        (cleared_count, evicted_count)
+    }
+
+    /// Evicts expired pre-commits and anchors as part of block initialization. The work is
+    /// capped by `EVICTION_BLOCK_WEIGHT_RATIO` of the maximum block weight, but every block
+    /// makes progress on at least one item. Returns the consumed weight.
+    fn evict_expired(now: T::BlockNumber) -> Weight {
+        let budget = EVICTION_BLOCK_WEIGHT_RATIO * T::MaximumBlockWeight::get();
+        let weight = Self::evict_expired_pre_commits(now, budget);
+        weight.saturating_add(Self::evict_expired_anchors(budget.saturating_sub(weight)))
+    }
+
+    /// Evicts pre-commits of the bucket at the eviction cursor once the bucket has expired.
+    /// The cursor moves to the next bucket when the current one is empty.
+    fn evict_expired_pre_commits(now: T::BlockNumber, budget: Weight) -> Weight {
+        let db_weight = T::DbWeight::get();
+        let mut weight = db_weight.reads(2);
+
+        let bucket_size = Self::pre_commit_eviction_bucket_size();
+        let mut bucket = Self::get_next_pre_commit_eviction_bucket();
+        if bucket.is_zero() {
+            bucket = bucket_size;
+        }
+        if bucket > now {
+            return weight;
+        }
+
+        // every pre-commit removal reads the pre-commit id and its position and writes the
+        // pre-commit, its position, the bucket and its index
+        let per_pre_commit = db_weight.reads_writes(2, 4);
+        let limit = budget
+            .saturating_sub(weight)
+            .checked_div(per_pre_commit)
+            .unwrap_or_else(T::MaxLoopInTx::get)
+            .max(1)
+            .min(T::MaxLoopInTx::get());
+        let pre_commits_count = Self::get_pre_commits_count_in_evict_bucket(bucket);
+        let (cleared_count, evicted_count) = Self::remove_pre_commits_in_bucket(bucket, limit);
+        weight = weight.saturating_add(per_pre_commit.saturating_mul(cleared_count));
+
+        if evicted_count > 0 {
+            Self::deposit_event(RawEvent::PreCommitsEvicted(bucket, evicted_count));
+        }
+        if cleared_count == pre_commits_count {
+            <NextPreCommitEvictionBucket<T>>::put(bucket.saturating_add(bucket_size));
+            weight = weight.saturating_add(db_weight.writes(1));
+        }
+
+        weight
+    }
+
+    /// Evicts the anchor child tries of the days after `LatestEvictedDate` up until yesterday,
+    /// followed by the indexes of the evicted anchors. The anchors of a day are removed a few at
+    /// a time, so a day with more anchors than the budget allows is evicted over several blocks.
+    fn evict_expired_anchors(budget: Weight) -> Weight {
+        let db_weight = T::DbWeight::get();
+        let mut weight = db_weight.reads(2);
+
+        let today_in_days_from_epoch = match Self::today_in_days_from_epoch() {
+            Ok(today) => today,
+            Err(_) => return weight,
+        };
+        let yesterday = today_in_days_from_epoch.saturating_sub(1);
+        let evict_date = LatestEvictedDate::get();
+
+        if evict_date < yesterday {
+            // every day reads and writes its root and eviction block, and every anchor of it is
+            // read and removed from its child trie, so a day might take several blocks
+            let per_day = db_weight
+                .reads_writes(2, 2)
+                .saturating_add(EVICT_ANCHOR_CHILD_TRIE_WEIGHT);
+            let per_anchor = db_weight
+                .reads_writes(1, 1)
+                .saturating_add(EVICT_ANCHOR_WEIGHT);
+            let mut until = evict_date;
+            while until < yesterday {
+                weight = weight.saturating_add(per_day);
+                let limit = budget
+                    .saturating_sub(weight)
+                    .checked_div(per_anchor)
+                    .unwrap_or(u64::max_value())
+                    .max(1);
+                let (removed_count, cleared) = Self::remove_anchors_of_day(until + 1, limit);
+                weight = weight.saturating_add(per_anchor.saturating_mul(removed_count));
+                if !cleared {
+                    break;
+                }
+                until += 1;
+                if weight >= budget {
+                    break;
+                }
+            }
+
+            if until > evict_date {
+                LatestEvictedDate::put(until);
+                weight = weight.saturating_add(db_weight.writes(1));
+                Self::deposit_event(RawEvent::AnchorsEvicted(evict_date + 1, until, until - evict_date));
+            }
+        }
+
+        // only go through the anchor indexes if the next one can actually be removed
+        let evict_date = LatestEvictedDate::get();
+        let next_index = LatestEvictedAnchorIndex::get() + 1;
+        weight = weight.saturating_add(db_weight.reads(4));
+        if next_index <= LatestAnchorIndex::get()
+            && <AnchorEvictDates<T>>::get(<AnchorIndexes<T>>::get(next_index)) <= evict_date
+        {
+            // every index removal reads the anchor id and date and removes both
+            let per_index = db_weight.reads_writes(2, 3);
+            let limit = budget
+                .saturating_sub(weight)
+                .checked_div(per_index)
+                .unwrap_or_else(T::MaxLoopInTx::get)
+                .max(1)
+                .min(T::MaxLoopInTx::get());
+            // indexes that are not evictable yet are read as well
+            weight = weight.saturating_add(per_index.saturating_mul(limit));
+            Self::remove_anchor_indexes_up_to(evict_date, limit as usize);
+        }
+
+        weight
+    }
+
     /// Iterate from the last evicted anchor to latest anchor, while removing indexes that
     /// are no longer valid because they belong to an expired/evicted anchor. The loop is
-    /// only allowed to run MAX_LOOP_IN_TX at a time.
+    /// only allowed to run `MaxLoopInTx` at a time.
     fn remove_anchor_indexes(yesterday: u32) -> usize {
+        Self::remove_anchor_indexes_up_to(yesterday, T::MaxLoopInTx::get() as usize)
+    }
+
+    /// Same as `remove_anchor_indexes`, but looks at `limit` anchor indexes at most.
+    fn remove_anchor_indexes_up_to(yesterday: u32, limit: usize) -> usize {
         (LatestEvictedAnchorIndex::get() + 1..LatestAnchorIndex::get() + 1)
-            // limit to only MAX_LOOP_IN_TX number of anchor indexes to remove
-            .take(MAX_LOOP_IN_TX as usize)
+            // limit the number of anchor indexes to remove
+            .take(limit)
             // get eviction date of the anchor given by index
             .map(|idx| {
                 (
@@ -391,15 +926,143 @@ impl<T: Trait> Module<T> {
             .count()
     }
 
-    /// Get an anchor by its id in the child storage
-    pub fn get_anchor_by_id(anchor_id: T::Hash) -> Option<AnchorData<T::Hash, T::BlockNumber>> {
+    /// Get an anchor by its id in the child storage. Fails if the stored anchor data can not
+    /// be decoded.
+    pub fn get_anchor_by_id(
+        anchor_id: T::Hash,
+    ) -> Result<Option<AnchorData<T::Hash, T::BlockNumber>>, DispatchError> {
         let anchor_evict_date = <AnchorEvictDates<T>>::get(anchor_id);
         let anchor_evict_date_enc: &[u8] = &anchor_evict_date.encode();
         let prefixed_key = Self::anchor_storage_key(anchor_evict_date_enc);
         let child_info = common::generate_child_storage_key(&prefixed_key);
 
         child::get_raw(&child_info, anchor_id.as_ref())
-            .map(|data| AnchorData::decode(&mut &*data).ok().unwrap())
+            .map(|data| {
+                AnchorData::decode(&mut &*data).map_err(|_e| Error::<T>::AnchorDataCorrupted.into())
+            })
+            .transpose()
+    }
+
+    /// Get an evicted anchor by its id from a `proof` of the child trie of its eviction `day`.
+    /// The proof is checked against the child trie root stored for that day on eviction.
+    pub fn get_evicted_anchor_by_id(
+        day: u32,
+        anchor_id: T::Hash,
+        proof: Vec<Vec<u8>>,
+    ) -> Result<AnchorData<T::Hash, T::BlockNumber>, DispatchError> {
+        Self::check_evicted_anchor_proof_limits(&proof)?;
+        ensure!(EvictedAnchorRoots::contains_key(day), Error::<T>::EvictedAnchorRootNotFound);
+        let root = T::Hash::decode(&mut &*EvictedAnchorRoots::get(day))
+            .map_err(|_e| Error::<T>::EvictedAnchorRootNotFound)?;
+
+        let db = StorageProof::new(proof).into_memory_db::<T::Hashing>();
+        let data = read_trie_value::<Layout<T::Hashing>, _>(&db, &root, anchor_id.as_ref())
+            .map_err(|_e| Error::<T>::InvalidEvictedAnchorProof)?
+            .ok_or(Error::<T>::InvalidEvictedAnchorProof)?;
+
+        AnchorData::decode(&mut &*data).map_err(|_e| Error::<T>::AnchorDataCorrupted.into())
+    }
+
+    /// Checks the number of trie nodes and the size of a proof of an evicted anchor against
+    /// `MaxEvictedAnchorProofNodes` and `MaxEvictedAnchorProofSize`, before any of them is
+    /// decoded.
+    pub fn check_evicted_anchor_proof_limits(proof: &[Vec<u8>]) -> DispatchResult {
+        let size = proof.iter().fold(0usize, |size, node| size.saturating_add(node.len()));
+        ensure!(
+            proof.len() <= T::MaxEvictedAnchorProofNodes::get() as usize
+                && size <= T::MaxEvictedAnchorProofSize::get() as usize,
+            Error::<T>::EvictedAnchorProofTooLarge
+        );
+        Ok(())
+    }
+
+    /// Computation weight of reading an evicted anchor from the given proof, which grows with
+    /// the number of its trie nodes and their size.
+    pub fn evicted_anchor_proof_weight(proof: &[Vec<u8>]) -> Weight {
+        let size = proof.iter().fold(0 as Weight, |size, node| size.saturating_add(node.len() as Weight));
+        EVICTED_ANCHOR_PROOF_WEIGHT_PER_NODE
+            .saturating_mul(proof.len() as Weight)
+            .saturating_add(EVICTED_ANCHOR_PROOF_WEIGHT_PER_BYTE.saturating_mul(size))
+    }
+
+    /// Verifies that `doc_root` was anchored as `anchor_id` in the child trie of `day`, which has
+    /// since been evicted. This allows proving the existence of a document long after its anchor
+    /// left the chain state.
+    pub fn verify_evicted_anchor(
+        day: u32,
+        anchor_id: T::Hash,
+        doc_root: T::Hash,
+        proof: Vec<Vec<u8>>,
+    ) -> DispatchResult {
+        let anchor_data = Self::get_evicted_anchor_by_id(day, anchor_id, proof)?;
+        ensure!(anchor_data.doc_root == doc_root, Error::<T>::EvictedAnchorDocRootMismatch);
+        Ok(())
+    }
+
+    /// Lists up to `limit` anchors starting at anchor index `start`. Indexes of evicted anchors are
+    /// skipped, so fewer anchors than `limit` might be returned before the latest index is reached.
+    pub fn get_anchors_by_index_range(
+        start: u64,
+        limit: u32,
+    ) -> Vec<AnchorWithEvictDate<T::Hash, T::BlockNumber>> {
+        // anchor indexes start at 1
+        let start = start.max(1);
+        let end = start
+            .saturating_add(limit.min(MAX_ANCHORS_PER_PAGE) as u64)
+            .min(LatestAnchorIndex::get().saturating_add(1));
+        (start..end)
+            .filter(|idx| <AnchorIndexes<T>>::contains_key(idx))
+            .map(|idx| <AnchorIndexes<T>>::get(idx))
+            .filter_map(|anchor_id| {
+                let evict_date = <AnchorEvictDates<T>>::get(anchor_id);
+                let anchor = Self::get_anchor_by_id(anchor_id).ok().flatten()?;
+                Some(AnchorWithEvictDate { anchor, evict_date })
+            })
+            .collect()
+    }
+
+    /// Lists up to `limit` anchors to be evicted on `day`, ordered by anchor id. The listing
+    /// continues after the anchor id `start_after` when given.
+    pub fn get_anchors_by_evict_date(
+        day: u32,
+        start_after: Option<T::Hash>,
+        limit: u32,
+    ) -> Vec<AnchorWithEvictDate<T::Hash, T::BlockNumber>> {
+        let child_info = Self::anchor_child_info(day);
+        let mut anchors = Vec::new();
+        let mut key = start_after.map_or(Vec::new(), |anchor_id| anchor_id.as_ref().to_vec());
+        while anchors.len() < limit.min(MAX_ANCHORS_PER_PAGE) as usize {
+            key = match sp_io::default_child_storage::next_key(child_info.storage_key(), &key) {
+                Some(next_key) => next_key,
+                None => break,
+            };
+            // anchors that can not be decoded are skipped
+            if let Some(anchor) = child::get_raw(&child_info, &key)
+                .and_then(|data| AnchorData::decode(&mut &*data).ok())
+            {
+                anchors.push(AnchorWithEvictDate { anchor, evict_date: day });
+            }
+        }
+        anchors
+    }
+
+    /// Counters of the anchor index.
+    pub fn get_anchor_counts() -> AnchorCounts {
+        AnchorCounts {
+            latest_index: LatestAnchorIndex::get(),
+            latest_evicted_index: LatestEvictedAnchorIndex::get(),
+        }
+    }
+
+    /// Storage key of the eviction date of `anchor_id`, which determines the child trie that
+    /// holds the anchor.
+    pub fn anchor_evict_date_key(anchor_id: T::Hash) -> Vec<u8> {
+        <AnchorEvictDates<T>>::hashed_key_for(anchor_id)
+    }
+
+    /// Child info of the child trie that holds the anchors to be evicted on `day`.
+    pub fn anchor_child_info(day: u32) -> ChildInfo {
+        common::generate_child_storage_key(&Self::anchor_storage_key(&day.encode()))
     }
 
     pub fn anchor_storage_key(storage_key: &[u8]) -> Vec<u8> {
@@ -409,10 +1072,129 @@ impl<T: Trait> Module<T> {
         prefixed_key
     }
 
-    fn store_anchor(anchor_id: T::Hash, prefixed_key: &Vec<u8>, stored_until_date_from_epoch: u32, anchor_data_encoded: &[u8]) {
-        let child_info = common::generate_child_storage_key(prefixed_key);
+    /// Validates a commit of `doc_root` for the anchor id obtained from `anchor_id_preimage`
+    /// on behalf of `who`. Returns the anchor id and the eviction date in days since epoch.
+    fn validate_commit(
+        who: &T::AccountId,
+        anchor_id_preimage: T::Hash,
+        doc_root: T::Hash,
+        proof: T::Hash,
+        stored_until_date: T::Moment,
+    ) -> Result<(T::Hash, u32), DispatchError> {
+        let stored_until_date_from_epoch = Self::validate_stored_until_date(stored_until_date)?;
+
+        let anchor_id = (anchor_id_preimage)
+            .using_encoded(<T as frame_system::Trait>::Hashing::hash);
+        ensure!(Self::get_anchor_by_id(anchor_id)?.is_none(), Error::<T>::AnchorAlreadyExists);
+
+        if Self::has_valid_pre_commit(anchor_id) {
+            ensure!(&<PreCommits<T>>::get(anchor_id).identity == who, Error::<T>::NotOwnerOfPreCommit);
+            ensure!(Self::has_valid_pre_commit_proof(anchor_id, doc_root, proof), Error::<T>::InvalidPreCommitProof);
+        }
+
+        Ok((anchor_id, stored_until_date_from_epoch))
+    }
+
+    /// Checks that an anchor committed now can be stored until the given date, which must be
+    /// more than a day from now and not after the maximum storage date. Returns the date in days
+    /// since epoch.
+    fn validate_stored_until_date(stored_until_date: T::Moment) -> Result<u32, DispatchError> {
+        let now_u64 = TryInto::<u64>::try_into(<pallet_timestamp::Module<T>>::get())
+            .map_err(|_e| Error::<T>::TimestampConversionFailed)?;
+        let eviction_date_u64 = TryInto::<u64>::try_into(stored_until_date)
+            .map_err(|_e| Error::<T>::TimestampConversionFailed)?;
+        ensure!(now_u64.saturating_add(common::MS_PER_DAY) < eviction_date_u64,
+            Error::<T>::EvictionDateTooEarly);
+
+        Self::stored_until_date_from_epoch(stored_until_date)
+    }
+
+    /// Converts a stored until date to days since epoch and checks that it is not after the
+    /// maximum storage date.
+    fn stored_until_date_from_epoch(stored_until_date: T::Moment) -> Result<u32, DispatchError> {
+        let eviction_date_u64 = TryInto::<u64>::try_into(stored_until_date)
+            .map_err(|_e| Error::<T>::TimestampConversionFailed)?;
+        let stored_until_date_from_epoch = common::get_days_since_epoch(eviction_date_u64);
+        ensure!(Self::anchor_storage_max_days_from_now() >= stored_until_date_from_epoch, Error::<T>::EvictionDateTooBig);
+
+        Ok(stored_until_date_from_epoch)
+    }
+
+    /// Account of the eviction bounty pool, funded by the share of the fees set by the fee
+    /// distributions, see `fees::Trait::EvictionBountyPoolAccount`.
+    pub fn eviction_bounty_pool_account() -> T::AccountId {
+        MODULE_ID.into_account()
+    }
+
+    /// Pays the state rent, split as set by the distribution of the anchoring fee.
+    fn pay_state_rent(who: T::AccountId, rent: <T as pallet_balances::Trait>::Balance) -> DispatchResult {
+        <fees::Module<T>>::distribute_fee(who, Self::fee_key(), rent)
+    }
+
+    /// Pays `who` a bounty of `bounty_per_item` for each of the `count` evicted items out of the
+    /// eviction bounty pool. Nothing is paid if nothing was evicted, and never more than what is
+    /// left in the pool.
+    fn pay_eviction_bounty(who: &T::AccountId, bounty_per_item: u128, count: u128) -> DispatchResult {
+        let pool = Self::eviction_bounty_pool_account();
+        let bounty: <T as pallet_balances::Trait>::Balance =
+            bounty_per_item.saturating_mul(count).saturated_into();
+        let bounty = bounty.min(<pallet_balances::Module<T> as Currency<_>>::free_balance(&pool));
+        if bounty.is_zero() {
+            return Ok(());
+        }
 
-        child::put_raw(&child_info, anchor_id.as_ref(), &anchor_data_encoded);
+        <pallet_balances::Module<T> as Currency<_>>::transfer(
+            &pool,
+            who,
+            bounty,
+            ExistenceRequirement::AllowDeath,
+        )?;
+        Self::deposit_event(RawEvent::EvictionBountyPaid(who.clone(), bounty));
+        Ok(())
+    }
+
+    /// Returns the state rent `commit` charges for an anchor stored until the given date, or the
+    /// error it fails with if the date is not valid or no anchoring fee is set.
+    pub fn commit_state_rent(
+        stored_until_date: T::Moment,
+    ) -> Result<<T as pallet_balances::Trait>::Balance, DispatchError> {
+        Self::state_rent(Self::validate_stored_until_date(stored_until_date)?)
+    }
+
+    /// Calculates the state rent for storing an anchor from today until the given day.
+    fn state_rent(
+        stored_until_date_from_epoch: u32,
+    ) -> Result<<T as pallet_balances::Trait>::Balance, DispatchError> {
+        let today_in_days_from_epoch = Self::today_in_days_from_epoch()?;
+        Self::rent_for_days(stored_until_date_from_epoch.saturating_sub(today_in_days_from_epoch))
+    }
+
+    /// Calculates the state rent for storing an anchor for the given number of days.
+    fn rent_for_days(days: u32) -> Result<<T as pallet_balances::Trait>::Balance, DispatchError> {
+        // we use the fee config setup on genesis for anchoring to calculate the state rent
+        let fee = <fees::Module<T>>::price_of(Self::fee_key()).ok_or(Error::<T>::AnchorFeeNotFound)?;
+        Ok(fee * <T as pallet_balances::Trait>::Balance::from(days))
+    }
+
+    /// Returns the current date as days since unix epoch.
+    fn today_in_days_from_epoch() -> Result<u32, DispatchError> {
+        TryInto::<u64>::try_into(<pallet_timestamp::Module<T>>::get())
+            .map(common::get_days_since_epoch)
+            .map_err(|_e| Error::<T>::TimestampConversionFailed.into())
+    }
+
+    /// Stores the anchor data in the child trie of its eviction date and updates the indexes.
+    fn store_anchor(anchor_id: T::Hash, doc_root: T::Hash, stored_until_date_from_epoch: u32) {
+        let anchor_data = AnchorData {
+            id: anchor_id,
+            doc_root: doc_root,
+            anchored_block: <frame_system::Module<T>>::block_number(),
+        };
+
+        let prefixed_key = Self::anchor_storage_key(&stored_until_date_from_epoch.encode());
+        let child_info = common::generate_child_storage_key(&prefixed_key);
+
+        child::put_raw(&child_info, anchor_id.as_ref(), &anchor_data.encode());
 
         // update indexes
         <AnchorEvictDates<T>>::insert(&anchor_id, &stored_until_date_from_epoch);
@@ -421,11 +1203,31 @@ impl<T: Trait> Module<T> {
         LatestAnchorIndex::put(idx);
     }
 
-    fn fee_key() -> <T as frame_system::Trait>::Hash {
+    /// Key of the anchoring state rent fee, charged per day an anchor is stored.
+    pub fn fee_key() -> <T as frame_system::Trait>::Hash {
         <T as frame_system::Trait>::Hashing::hash_of(&0)
     }
+
+    /// Fee keys of this module to register in the fees module. The price of the state rent is
+    /// set at genesis, see `fees::GenesisConfig::initial_fees`.
+    pub fn fee_key_registrations(
+    ) -> Vec<fees::FeeKeyRegistration<<T as frame_system::Trait>::Hash, <T as pallet_balances::Trait>::Balance>> {
+        sp_std::vec![fees::FeeKeyRegistration {
+            key: Self::fee_key(),
+            info: fees::FeeKeyInfo {
+                name: b"anchor_state_rent".to_vec(),
+                unit: fees::FeeUnit::PerDay,
+                pallet: b"anchor".to_vec(),
+            },
+            initial_price: None,
+            initial_distribution: None,
+        }]
+    }
 }
 
 /// tests for anchor module
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
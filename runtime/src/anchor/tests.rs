@@ -1,7 +1,10 @@
 use super::*;
 
+use crate::anchor;
 use frame_support::{
-    assert_err, assert_ok, impl_outer_origin, parameter_types, traits::Randomness, weights::Weight,
+    assert_err, assert_ok, impl_outer_event, impl_outer_origin, parameter_types,
+    traits::{Currency, OnInitialize, Randomness},
+    weights::Weight,
 };
 use sp_core::H256;
 use sp_runtime::{
@@ -9,12 +12,22 @@ use sp_runtime::{
     traits::{BadOrigin, BlakeTwo256, IdentityLookup},
     Perbill,
 };
+use sp_trie::{MemoryDB, TrieDBMut, TrieMut};
 use std::time::Instant;
 
 impl_outer_origin! {
     pub enum Origin for Test {}
 }
 
+impl_outer_event! {
+    pub enum TestEvent for Test {
+        frame_system<T>,
+        pallet_balances<T>,
+        fees<T>,
+        anchor<T>,
+    }
+}
+
 // For testing the module, we construct most of a mock runtime. This means
 // first constructing a configuration type (`Test`) which `impl`s each of the
 // configuration traits of modules we want to use.
@@ -35,7 +48,7 @@ impl frame_system::Trait for Test {
     type Hash = H256;
     type Hashing = BlakeTwo256;
     type Header = Header;
-    type Event = ();
+    type Event = TestEvent;
     type Origin = Origin;
     type BlockHashCount = BlockHashCount;
     type MaximumBlockWeight = MaximumBlockWeight;
@@ -61,9 +74,25 @@ impl pallet_timestamp::Trait for Test {
     type WeightInfo = ();
 }
 
+parameter_types! {
+    pub EvictionBountyPoolAccount: u64 = Anchor::eviction_bounty_pool_account();
+    // 10% of the state rent goes to the eviction bounty pool
+    pub const DefaultFeeDistribution: fees::FeeDistribution = fees::FeeDistribution {
+        author: Perbill::from_percent(90),
+        treasury: Perbill::from_percent(0),
+        eviction_bounty_pool: Perbill::from_percent(10),
+        burn: Perbill::from_percent(0),
+    };
+    pub SponsoredFeeKeys: Vec<H256> = vec![Anchor::fee_key()];
+}
 impl fees::Trait for Test {
-    type Event = ();
+    type Event = TestEvent;
     type FeeChangeOrigin = frame_system::EnsureRoot<u64>;
+    type EvictionBountyPoolAccount = EvictionBountyPoolAccount;
+    type DefaultFeeDistribution = DefaultFeeDistribution;
+    type FeeKeyRegistrations = ();
+    type MinFeeChangeNotice = ();
+    type SponsoredFeeKeys = SponsoredFeeKeys;
 }
 
 parameter_types! {
@@ -72,7 +101,7 @@ parameter_types! {
 impl pallet_balances::Trait for Test {
     type Balance = u64;
     type DustRemoval = ();
-    type Event = ();
+    type Event = TestEvent;
     type ExistentialDeposit = ExistentialDeposit;
     type AccountStore = System;
     type WeightInfo = ();
@@ -85,7 +114,25 @@ impl pallet_authorship::Trait for Test {
     type EventHandler = ();
 }
 
-impl Trait for Test {}
+parameter_types! {
+    pub const PreCommitExpirationDurationBlocks: u64 = 800;
+    pub const PreCommitEvictionBucketMultiplier: u32 = 5;
+    pub const MaxLoopInTx: u64 = 500;
+    pub const StorageMaxDays: u32 = 376200;
+    pub const MaxEvictedAnchorProofNodes: u32 = 16;
+    pub const MaxEvictedAnchorProofSize: u32 = 16 * 1024;
+}
+impl Trait for Test {
+    type Event = TestEvent;
+    type WeightInfo = ();
+    type PreCommitExpirationDurationBlocks = PreCommitExpirationDurationBlocks;
+    type PreCommitEvictionBucketMultiplier = PreCommitEvictionBucketMultiplier;
+    type MaxLoopInTx = MaxLoopInTx;
+    type StorageMaxDays = StorageMaxDays;
+    type MaxEvictedAnchorProofNodes = MaxEvictedAnchorProofNodes;
+    type MaxEvictedAnchorProofSize = MaxEvictedAnchorProofSize;
+    type AdminOrigin = frame_system::EnsureRoot<u64>;
+}
 
 impl Test {
     fn test_document_hashes() -> (
@@ -144,6 +191,13 @@ fn new_test_ext() -> sp_io::TestExternalities {
     ext
 }
 
+fn last_event() -> TestEvent {
+    System::events()
+        .pop()
+        .map(|e| e.event)
+        .expect("Event expected")
+}
+
 #[test]
 fn basic_pre_commit() {
     new_test_ext().execute_with(|| {
@@ -191,7 +245,7 @@ fn pre_commit_fail_anchor_exists() {
         // fails because of existing anchor
         assert_err!(
             Anchor::pre_commit(Origin::signed(1), anchor_id, signing_root),
-            "Anchor already exists"
+            Error::<Test>::AnchorAlreadyExists
         );
     });
 }
@@ -214,7 +268,7 @@ fn pre_commit_fail_anchor_exists_different_acc() {
         // fails because of existing anchor
         assert_err!(
             Anchor::pre_commit(Origin::signed(1), anchor_id, signing_root),
-            "Anchor already exists"
+            Error::<Test>::AnchorAlreadyExists
         );
     });
 }
@@ -242,7 +296,7 @@ fn pre_commit_fail_pre_commit_exists() {
         // fail, pre-commit exists
         assert_err!(
             Anchor::pre_commit(Origin::signed(1), anchor_id, signing_root),
-            "A valid pre-commit already exists"
+            Error::<Test>::PreCommitAlreadyExists
         );
 
         // expire the pre-commit
@@ -278,7 +332,7 @@ fn pre_commit_fail_pre_commit_exists_different_acc() {
         // fail, pre-commit exists
         assert_err!(
             Anchor::pre_commit(Origin::signed(2), anchor_id, signing_root),
-            "A valid pre-commit already exists"
+            Error::<Test>::PreCommitAlreadyExists
         );
 
         // expire the pre-commit
@@ -320,7 +374,7 @@ fn basic_commit() {
             1567589834087
         ));
         // asserting that the stored anchor id is what we sent the pre-image for
-        let mut a = Anchor::get_anchor_by_id(anchor_id).unwrap();
+        let mut a = Anchor::get_anchor_by_id(anchor_id).unwrap().unwrap();
         assert_eq!(a.id, anchor_id);
         assert_eq!(a.doc_root, doc_root);
         assert_eq!(Anchor::get_anchor_evict_date(anchor_id), 18144);
@@ -338,7 +392,7 @@ fn basic_commit() {
             <Test as frame_system::Trait>::Hashing::hash_of(&0),
             1567589844087
         ));
-        a = Anchor::get_anchor_by_id(anchor_id2).unwrap();
+        a = Anchor::get_anchor_by_id(anchor_id2).unwrap().unwrap();
         assert_eq!(a.id, anchor_id2);
         assert_eq!(a.doc_root, doc_root);
         assert_eq!(Anchor::get_anchor_evict_date(anchor_id2), 18144);
@@ -357,153 +411,912 @@ fn basic_commit() {
                 <Test as frame_system::Trait>::Hashing::hash_of(&0),
                 2 // some arbitrary store until date that is less than the required minimum
             ),
-            "Stored until date must be at least a day later than the current date"
+            Error::<Test>::EvictionDateTooEarly
+        );
+    });
+}
+
+#[test]
+fn commit_fail_anchor_exists() {
+    new_test_ext().execute_with(|| {
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+
+        // happy
+        assert_ok!(Anchor::commit(
+            Origin::signed(1),
+            pre_image,
+            doc_root,
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            common::MS_PER_DAY + 1
+        ));
+        // asserting that the stored anchor id is what we sent the pre-image for
+        let a = Anchor::get_anchor_by_id(anchor_id).unwrap().unwrap();
+        assert_eq!(a.id, anchor_id);
+        assert_eq!(a.doc_root, doc_root);
+
+        assert_err!(
+            Anchor::commit(
+                Origin::signed(1),
+                pre_image,
+                doc_root,
+                <Test as frame_system::Trait>::Hashing::hash_of(&0),
+                common::MS_PER_DAY + 1
+            ),
+            Error::<Test>::AnchorAlreadyExists
+        );
+
+        // different acc
+        assert_err!(
+            Anchor::commit(
+                Origin::signed(2),
+                pre_image,
+                doc_root,
+                <Test as frame_system::Trait>::Hashing::hash_of(&0),
+                common::MS_PER_DAY + 1
+            ),
+            Error::<Test>::AnchorAlreadyExists
+        );
+    });
+}
+
+#[test]
+fn basic_pre_commit_commit() {
+    new_test_ext().execute_with(|| {
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        let random_doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let (doc_root, signing_root, proof) = Test::test_document_hashes();
+
+        // happy
+        assert_ok!(Anchor::pre_commit(
+            Origin::signed(1),
+            anchor_id,
+            signing_root
+        ));
+
+        // wrong doc root
+        assert_err!(
+            Anchor::commit(
+                Origin::signed(1),
+                pre_image,
+                random_doc_root,
+                proof,
+                common::MS_PER_DAY + 1
+            ),
+            Error::<Test>::InvalidPreCommitProof
+        );
+
+        // happy
+        assert_ok!(Anchor::commit(
+            Origin::signed(1),
+            pre_image,
+            doc_root,
+            proof,
+            common::MS_PER_DAY + 1
+        ));
+        // asserting that the stored anchor id is what we sent the pre-image for
+        let a = Anchor::get_anchor_by_id(anchor_id).unwrap().unwrap();
+        assert_eq!(a.id, anchor_id);
+        assert_eq!(a.doc_root, doc_root);
+    });
+}
+
+#[test]
+fn pre_commit_expired_when_anchoring() {
+    new_test_ext().execute_with(|| {
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        let (doc_root, signing_root, proof) = Test::test_document_hashes();
+
+        // happy
+        assert_ok!(Anchor::pre_commit(
+            Origin::signed(1),
+            anchor_id,
+            signing_root
+        ));
+        // expire the pre-commit
+        System::set_block_number(Anchor::pre_commit_expiration_duration_blocks() + 2);
+
+        // happy from a different account
+        assert_ok!(Anchor::commit(
+            Origin::signed(2),
+            pre_image,
+            doc_root,
+            proof,
+            common::MS_PER_DAY + 1
+        ));
+        // asserting that the stored anchor id is what we sent the pre-image for
+        let a = Anchor::get_anchor_by_id(anchor_id).unwrap().unwrap();
+        assert_eq!(a.id, anchor_id);
+        assert_eq!(a.doc_root, doc_root);
+    });
+}
+
+#[test]
+fn pre_commit_expiration_duration_override() {
+    new_test_ext().execute_with(|| {
+        let signing_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+
+        // only the admin origin can change the duration
+        assert_err!(
+            Anchor::set_pre_commit_expiration_duration(Origin::signed(1), Some(100)),
+            BadOrigin
+        );
+        assert_err!(
+            Anchor::set_pre_commit_expiration_duration(Origin::root(), Some(0)),
+            Error::<Test>::InvalidPreCommitExpirationDuration
+        );
+
+        assert_ok!(Anchor::set_pre_commit_expiration_duration(
+            Origin::root(),
+            Some(100)
+        ));
+        assert_eq!(
+            last_event(),
+            TestEvent::anchor(RawEvent::PreCommitExpirationDurationChanged(Some(100)))
+        );
+        let anchor_id = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        assert_ok!(Anchor::pre_commit(
+            Origin::signed(1),
+            anchor_id,
+            signing_root
+        ));
+        let a = Anchor::get_pre_commit(anchor_id);
+        assert_eq!(a.expiration_block, 101);
+        // the eviction bucket still follows the configured duration
+        assert_eq!(
+            Anchor::get_pre_commits_count_in_evict_bucket(
+                PreCommitExpirationDurationBlocks::get()
+                    * PreCommitEvictionBucketMultiplier::get() as u64
+            ),
+            1
+        );
+
+        // removing the override restores the configured duration
+        assert_ok!(Anchor::set_pre_commit_expiration_duration(
+            Origin::root(),
+            None
+        ));
+        assert_eq!(
+            last_event(),
+            TestEvent::anchor(RawEvent::PreCommitExpirationDurationChanged(None))
+        );
+        let anchor_id = <Test as frame_system::Trait>::Hashing::hash_of(&1);
+        assert_ok!(Anchor::pre_commit(
+            Origin::signed(1),
+            anchor_id,
+            signing_root
+        ));
+        let a = Anchor::get_pre_commit(anchor_id);
+        assert_eq!(
+            a.expiration_block,
+            PreCommitExpirationDurationBlocks::get() + 1
+        );
+    });
+}
+
+#[test]
+fn cancel_pre_commit() {
+    new_test_ext().execute_with(|| {
+        let signing_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let anchor_ids = (0..3)
+            .map(|i| <Test as frame_system::Trait>::Hashing::hash_of(&i))
+            .collect::<Vec<_>>();
+        for anchor_id in &anchor_ids {
+            assert_ok!(Anchor::pre_commit(
+                Origin::signed(1),
+                *anchor_id,
+                signing_root
+            ));
+        }
+        let bucket = Anchor::determine_pre_commit_eviction_bucket(
+            Anchor::pre_commit_expiration_duration_blocks() + 1,
+        )
+        .unwrap();
+        assert_eq!(Anchor::get_pre_commits_count_in_evict_bucket(bucket), 3);
+
+        // reject unsigned, unknown pre-commits and other accounts
+        assert_err!(
+            Anchor::cancel_pre_commit(Origin::none(), anchor_ids[0]),
+            BadOrigin
+        );
+        assert_err!(
+            Anchor::cancel_pre_commit(
+                Origin::signed(1),
+                <Test as frame_system::Trait>::Hashing::hash_of(&3)
+            ),
+            Error::<Test>::PreCommitNotFound
+        );
+        assert_err!(
+            Anchor::cancel_pre_commit(Origin::signed(2), anchor_ids[0]),
+            Error::<Test>::NotOwnerOfPreCommit
+        );
+
+        // the last pre-commit of the bucket takes the position of the cancelled one
+        assert_ok!(Anchor::cancel_pre_commit(Origin::signed(1), anchor_ids[0]));
+        assert_eq!(
+            last_event(),
+            TestEvent::anchor(RawEvent::PreCommitCancelled(anchor_ids[0], 1))
+        );
+        assert!(!<PreCommits<Test>>::contains_key(anchor_ids[0]));
+        assert_eq!(Anchor::get_pre_commit_eviction_bucket_position(anchor_ids[0]), None);
+        assert_eq!(Anchor::get_pre_commits_count_in_evict_bucket(bucket), 2);
+        assert_eq!(
+            Anchor::get_pre_commit_in_evict_bucket_by_index((bucket, 0)),
+            anchor_ids[2]
+        );
+        assert_eq!(
+            Anchor::get_pre_commit_eviction_bucket_position(anchor_ids[2]),
+            Some((bucket, 0))
+        );
+        assert!(!<PreCommitEvictionBuckets<Test>>::contains_key((bucket, 2)));
+
+        // cancelling the last pre-commit of the bucket does not move any other
+        assert_ok!(Anchor::cancel_pre_commit(Origin::signed(1), anchor_ids[1]));
+        assert_eq!(Anchor::get_pre_commits_count_in_evict_bucket(bucket), 1);
+        assert_eq!(
+            Anchor::get_pre_commit_in_evict_bucket_by_index((bucket, 0)),
+            anchor_ids[2]
+        );
+
+        // the anchor id is free to be pre-committed by others
+        assert_ok!(Anchor::pre_commit(
+            Origin::signed(2),
+            anchor_ids[0],
+            signing_root
+        ));
+
+        // eviction removes the remaining pre-commits of the bucket
+        System::set_block_number(bucket + 1);
+        assert_ok!(Anchor::evict_pre_commits(Origin::signed(1), bucket));
+        assert_eq!(Anchor::get_pre_commits_count_in_evict_bucket(bucket), 0);
+        assert!(!<PreCommits<Test>>::contains_key(anchor_ids[0]));
+        assert!(!<PreCommits<Test>>::contains_key(anchor_ids[2]));
+        assert_eq!(Anchor::get_pre_commit_eviction_bucket_position(anchor_ids[2]), None);
+    });
+}
+
+#[test]
+fn evict_pre_commits_keeps_later_pre_commit_of_same_anchor() {
+    new_test_ext().execute_with(|| {
+        let anchor_id = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let signing_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let bucket_size = PreCommitExpirationDurationBlocks::get()
+            * PreCommitEvictionBucketMultiplier::get() as u64;
+
+        // pre-commit expiring right before the end of the first bucket
+        System::set_block_number(bucket_size - PreCommitExpirationDurationBlocks::get() - 1);
+        assert_ok!(Anchor::pre_commit(
+            Origin::signed(1),
+            anchor_id,
+            signing_root
+        ));
+
+        // pre-commit again once expired, which lands in the second bucket
+        System::set_block_number(bucket_size - 1);
+        assert_ok!(Anchor::pre_commit(
+            Origin::signed(2),
+            anchor_id,
+            signing_root
+        ));
+        assert_eq!(
+            Anchor::get_pre_commit_eviction_bucket_position(anchor_id),
+            Some((bucket_size * 2, 0))
         );
+
+        // the skipped entry is neither counted nor paid for
+        let pool = Anchor::eviction_bounty_pool_account();
+        let pool_balance = 100 * PRE_COMMIT_EVICTION_BOUNTY as u64;
+        <pallet_balances::Module<Test>>::make_free_balance_be(&pool, pool_balance);
+        <pallet_balances::Module<Test>>::make_free_balance_be(&3, 1);
+        System::set_block_number(bucket_size + 1);
+        assert_ok!(Anchor::evict_pre_commits(Origin::signed(3), bucket_size));
+        assert_eq!(Anchor::get_pre_commits_count_in_evict_bucket(bucket_size), 0);
+        assert_eq!(Anchor::get_pre_commit(anchor_id).identity, 2);
+        assert!(Anchor::has_valid_pre_commit(anchor_id));
+        assert_eq!(
+            last_event(),
+            TestEvent::anchor(RawEvent::PreCommitsEvicted(bucket_size, 0))
+        );
+        assert_eq!(<pallet_balances::Module<Test>>::free_balance(&3), 1);
+    });
+}
+
+#[test]
+fn pre_commit_commit_fail_from_another_acc() {
+    new_test_ext().execute_with(|| {
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        let (doc_root, signing_root, proof) = Test::test_document_hashes();
+
+        // happy
+        assert_ok!(Anchor::pre_commit(
+            Origin::signed(1),
+            anchor_id,
+            signing_root
+        ));
+
+        // fail from a different account
+        assert_err!(
+            Anchor::commit(
+                Origin::signed(2),
+                pre_image,
+                doc_root,
+                proof,
+                common::MS_PER_DAY + 1
+            ),
+            Error::<Test>::NotOwnerOfPreCommit
+        );
+    });
+}
+
+#[test]
+fn pre_commit_and_commit_emit_events() {
+    new_test_ext().execute_with(|| {
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        let (doc_root, signing_root, proof) = Test::test_document_hashes();
+
+        assert_ok!(Anchor::pre_commit(
+            Origin::signed(1),
+            anchor_id,
+            signing_root
+        ));
+        assert_eq!(
+            last_event(),
+            TestEvent::anchor(RawEvent::PreCommitted(
+                anchor_id,
+                1,
+                Anchor::pre_commit_expiration_duration_blocks() + 1
+            ))
+        );
+
+        assert_ok!(Anchor::commit(
+            Origin::signed(1),
+            pre_image,
+            doc_root,
+            proof,
+            common::MS_PER_DAY + 1
+        ));
+        assert_eq!(
+            last_event(),
+            TestEvent::anchor(RawEvent::AnchorCommitted(anchor_id, doc_root, 2))
+        );
+    });
+}
+
+#[test]
+fn evictions_emit_events() {
+    new_test_ext().execute_with(|| {
+        let day = |n| common::MS_PER_DAY * n + 1;
+        let (doc_root, signing_root, proof) = Test::test_document_hashes();
+
+        assert_ok!(Anchor::pre_commit(
+            Origin::signed(1),
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            signing_root
+        ));
+        let bucket = Anchor::determine_pre_commit_eviction_bucket(1).unwrap();
+        System::set_block_number(bucket);
+        assert_ok!(Anchor::evict_pre_commits(Origin::signed(1), bucket));
+        assert_eq!(
+            last_event(),
+            TestEvent::anchor(RawEvent::PreCommitsEvicted(bucket, 1))
+        );
+
+        assert_ok!(Anchor::commit(
+            Origin::signed(1),
+            <Test as frame_system::Trait>::Hashing::hash_of(&1),
+            doc_root,
+            proof,
+            day(1)
+        ));
+        <pallet_timestamp::Module<Test>>::set_timestamp(day(3));
+        assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
+        assert_eq!(
+            last_event(),
+            TestEvent::anchor(RawEvent::AnchorsEvicted(1, 3, 3))
+        );
+    });
+}
+
+/// Builds a proof for the given anchor out of a child trie that only contains this anchor. Must
+/// be called before the anchors of `day` are evicted.
+pub(crate) fn evicted_anchor_proof<T: Trait>(day: u32, anchor_id: T::Hash) -> Vec<Vec<u8>> {
+    let anchor_data = child::get_raw(&Module::<T>::anchor_child_info(day), anchor_id.as_ref())
+        .expect("anchor must be stored on the given day");
+    let mut db = MemoryDB::<T::Hashing>::default();
+    let mut root = Default::default();
+    {
+        let mut trie = TrieDBMut::<Layout<T::Hashing>>::new(&mut db, &mut root);
+        trie.insert(anchor_id.as_ref(), &anchor_data).unwrap();
+    }
+    db.drain().into_iter().map(|(_, (node, _))| node).collect()
+}
+
+#[test]
+fn verify_evicted_anchor() {
+    new_test_ext().execute_with(|| {
+        let day = |n| common::MS_PER_DAY * n + 1;
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&1);
+
+        // stored until day 2
+        assert_ok!(Anchor::commit(
+            Origin::signed(1),
+            pre_image,
+            doc_root,
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            day(1)
+        ));
+        let proof = evicted_anchor_proof::<Test>(2, anchor_id);
+
+        // not evicted yet
+        assert_err!(
+            Anchor::verify_evicted_anchor(2, anchor_id, doc_root, proof.clone()),
+            Error::<Test>::EvictedAnchorRootNotFound
+        );
+
+        System::set_block_number(10);
+        <pallet_timestamp::Module<Test>>::set_timestamp(day(3));
+        assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
+        assert!(Anchor::get_anchor_by_id(anchor_id).unwrap().is_none());
+        assert_eq!(Anchor::get_evicted_anchor_block_by_day(2), Some(10));
+
+        // happy
+        assert_ok!(Anchor::verify_evicted_anchor(
+            2,
+            anchor_id,
+            doc_root,
+            proof.clone()
+        ));
+        assert_eq!(
+            Anchor::get_evicted_anchor_by_id(2, anchor_id, proof.clone())
+                .unwrap()
+                .doc_root,
+            doc_root
+        );
+
+        // wrong document root
+        assert_err!(
+            Anchor::verify_evicted_anchor(2, anchor_id, pre_image, proof.clone()),
+            Error::<Test>::EvictedAnchorDocRootMismatch
+        );
+
+        // proof against the root of another day
+        assert_err!(
+            Anchor::verify_evicted_anchor(3, anchor_id, doc_root, proof.clone()),
+            Error::<Test>::InvalidEvictedAnchorProof
+        );
+
+        // no root stored for the day
+        assert_err!(
+            Anchor::verify_evicted_anchor(5, anchor_id, doc_root, proof.clone()),
+            Error::<Test>::EvictedAnchorRootNotFound
+        );
+
+        // anchor not part of the proof
+        assert_err!(
+            Anchor::verify_evicted_anchor(2, pre_image, doc_root, proof),
+            Error::<Test>::InvalidEvictedAnchorProof
+        );
+        assert_err!(
+            Anchor::verify_evicted_anchor(2, anchor_id, doc_root, vec![]),
+            Error::<Test>::InvalidEvictedAnchorProof
+        );
+
+        // too many trie nodes or bytes
+        let mut too_many_nodes = proof.clone();
+        too_many_nodes.resize(MaxEvictedAnchorProofNodes::get() as usize + 1, vec![0]);
+        assert_err!(
+            Anchor::verify_evicted_anchor(2, anchor_id, doc_root, too_many_nodes),
+            Error::<Test>::EvictedAnchorProofTooLarge
+        );
+        let mut too_many_bytes = proof.clone();
+        too_many_bytes.push(vec![0; MaxEvictedAnchorProofSize::get() as usize]);
+        assert_err!(
+            Anchor::verify_evicted_anchor(2, anchor_id, doc_root, too_many_bytes),
+            Error::<Test>::EvictedAnchorProofTooLarge
+        );
+    });
+}
+
+fn batch_commit(
+    seed: u64,
+    doc_root: <Test as frame_system::Trait>::Hash,
+    proof: <Test as frame_system::Trait>::Hash,
+    stored_until_date: u64,
+) -> AnchorCommit<<Test as frame_system::Trait>::Hash, u64> {
+    AnchorCommit {
+        anchor_id_preimage: <Test as frame_system::Trait>::Hashing::hash_of(&seed),
+        doc_root,
+        proof,
+        stored_until_date,
+    }
+}
+
+#[test]
+fn commit_batch_all_or_nothing() {
+    new_test_ext().execute_with(|| {
+        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let proof = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let commits: Vec<_> = (0..3)
+            .map(|i| batch_commit(i, doc_root, proof, common::MS_PER_DAY + 1))
+            .collect();
+
+        // reject unsigned
+        assert_err!(
+            Anchor::commit_batch(Origin::none(), commits.clone(), true),
+            BadOrigin
+        );
+
+        // happy
+        assert_ok!(Anchor::commit_batch(Origin::signed(1), commits.clone(), true));
+        assert_eq!(Anchor::get_latest_anchor_index(), 3);
+        for (i, c) in commits.iter().enumerate() {
+            let anchor_id =
+                (c.anchor_id_preimage).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+            let a = Anchor::get_anchor_by_id(anchor_id).unwrap().unwrap();
+            assert_eq!(a.id, anchor_id);
+            assert_eq!(a.doc_root, doc_root);
+            assert_eq!(Anchor::get_anchor_id_by_index(i as u64 + 1), anchor_id);
+        }
+
+        // a single existing anchor fails the whole batch
+        let commits = vec![
+            batch_commit(3, doc_root, proof, common::MS_PER_DAY + 1),
+            batch_commit(0, doc_root, proof, common::MS_PER_DAY + 1),
+        ];
+        assert_err!(
+            Anchor::commit_batch(Origin::signed(1), commits, true),
+            Error::<Test>::AnchorAlreadyExists
+        );
+        assert_eq!(Anchor::get_latest_anchor_index(), 3);
+
+        // duplicates within a batch fail as well
+        let commits = vec![
+            batch_commit(4, doc_root, proof, common::MS_PER_DAY + 1),
+            batch_commit(4, doc_root, proof, common::MS_PER_DAY + 1),
+        ];
+        assert_err!(
+            Anchor::commit_batch(Origin::signed(1), commits, true),
+            Error::<Test>::AnchorAlreadyExists
+        );
+        assert_eq!(Anchor::get_latest_anchor_index(), 3);
+    });
+}
+
+#[test]
+fn commit_batch_best_effort() {
+    new_test_ext().execute_with(|| {
+        let (doc_root, signing_root, proof) = Test::test_document_hashes();
+        let random_doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+
+        assert_ok!(Anchor::commit_batch(
+            Origin::signed(1),
+            vec![batch_commit(0, doc_root, proof, common::MS_PER_DAY + 1)],
+            false
+        ));
+
+        // pre-commit an anchor with account 2 to make the commit fail for account 1
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&2);
+        let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        assert_ok!(Anchor::pre_commit(Origin::signed(2), anchor_id, signing_root));
+
+        let commits = vec![
+            // already anchored
+            batch_commit(0, doc_root, proof, common::MS_PER_DAY + 1),
+            batch_commit(1, doc_root, proof, common::MS_PER_DAY + 1),
+            // pre-commit owned by someone else
+            batch_commit(2, doc_root, proof, common::MS_PER_DAY + 1),
+            // stored until date too early
+            batch_commit(3, doc_root, proof, 2),
+            // duplicate within the batch
+            batch_commit(1, random_doc_root, proof, common::MS_PER_DAY + 1),
+            batch_commit(4, random_doc_root, proof, common::MS_PER_DAY + 1),
+        ];
+        assert_ok!(Anchor::commit_batch(Origin::signed(1), commits, false));
+        assert_eq!(Anchor::get_latest_anchor_index(), 3);
+
+        let anchor_id_1 = (<Test as frame_system::Trait>::Hashing::hash_of(&1))
+            .using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        assert_eq!(Anchor::get_anchor_by_id(anchor_id_1).unwrap().unwrap().doc_root, doc_root);
+        assert!(Anchor::get_anchor_by_id(anchor_id).unwrap().is_none());
+        let anchor_id_4 = (<Test as frame_system::Trait>::Hashing::hash_of(&4))
+            .using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        assert_eq!(
+            Anchor::get_anchor_by_id(anchor_id_4).unwrap().unwrap().doc_root,
+            random_doc_root
+        );
+
+        // a batch without any valid commit fails
+        assert_err!(
+            Anchor::commit_batch(
+                Origin::signed(1),
+                vec![batch_commit(0, doc_root, proof, common::MS_PER_DAY + 1)],
+                false
+            ),
+            Error::<Test>::NoValidCommitInBatch
+        );
+    });
+}
+
+#[test]
+fn commit_batch_size_limits() {
+    new_test_ext().execute_with(|| {
+        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let proof = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+
+        assert_err!(
+            Anchor::commit_batch(Origin::signed(1), vec![], true),
+            Error::<Test>::EmptyBatch
+        );
+
+        let commits: Vec<_> = (0..MAX_ANCHORS_IN_BATCH as u64 + 1)
+            .map(|i| batch_commit(i, doc_root, proof, common::MS_PER_DAY + 1))
+            .collect();
+        assert_err!(
+            Anchor::commit_batch(Origin::signed(1), commits, false),
+            Error::<Test>::BatchTooLarge
+        );
+    });
+}
+
+#[test]
+fn commit_batch_pays_state_rent_once() {
+    new_test_ext().execute_with(|| {
+        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let proof = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let day = |n| common::MS_PER_DAY * n + 1;
+
+        // 10 per day of storage, default author of the test runtime is account 0
+        assert_ok!(<fees::Module<Test>>::set_fee(Origin::root(), Anchor::fee_key(), 10));
+        <pallet_balances::Module<Test>>::make_free_balance_be(&1, 1000);
+        <pallet_balances::Module<Test>>::make_free_balance_be(&0, 1);
+
+        let commits = vec![
+            // stored for 2 days
+            batch_commit(0, doc_root, proof, day(1)),
+            // stored for 5 days
+            batch_commit(1, doc_root, proof, day(4)),
+        ];
+        assert_ok!(Anchor::commit_batch(Origin::signed(1), commits, true));
+
+        // 10% of the state rent goes to the eviction bounty pool
+        assert_eq!(<pallet_balances::Module<Test>>::free_balance(&1), 1000 - 70);
+        assert_eq!(<pallet_balances::Module<Test>>::free_balance(&0), 1 + 63);
+        assert_eq!(
+            <pallet_balances::Module<Test>>::free_balance(&Anchor::eviction_bounty_pool_account()),
+            7
+        );
+    });
+}
+
+#[test]
+fn commit_state_rent_matches_rent_paid() {
+    new_test_ext().execute_with(|| {
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&1);
+        let day = |n| common::MS_PER_DAY * n + 1;
+
+        // 10 per day of storage
+        assert_ok!(<fees::Module<Test>>::set_fee(Origin::root(), Anchor::fee_key(), 10));
+        <pallet_balances::Module<Test>>::make_free_balance_be(&1, 1000);
+
+        assert_err!(Anchor::commit_state_rent(day(0)), Error::<Test>::EvictionDateTooEarly);
+        assert_err!(
+            Anchor::commit_state_rent(day(StorageMaxDays::get() as u64 + 1)),
+            Error::<Test>::EvictionDateTooBig
+        );
+        assert_eq!(Anchor::commit_state_rent(day(4)), Ok(50));
+
+        assert_ok!(Anchor::commit(
+            Origin::signed(1),
+            pre_image,
+            doc_root,
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            day(4)
+        ));
+        assert_eq!(<pallet_balances::Module<Test>>::free_balance(&1), 1000 - 50);
+    });
+}
+
+#[test]
+fn commit_state_rent_paid_by_sponsor() {
+    new_test_ext().execute_with(|| {
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&1);
+        let day = |n| common::MS_PER_DAY * n + 1;
+
+        // 10 per day of storage, account 5 has no funds and is sponsored by account 1
+        assert_ok!(<fees::Module<Test>>::set_fee(Origin::root(), Anchor::fee_key(), 10));
+        <pallet_balances::Module<Test>>::make_free_balance_be(&1, 1000);
+        assert_ok!(<fees::Module<Test>>::grant_allowance(Origin::signed(1), 5, Some(100), None));
+
+        assert_ok!(Anchor::commit(
+            Origin::signed(5),
+            pre_image,
+            doc_root,
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            day(4)
+        ));
+        assert_eq!(<pallet_balances::Module<Test>>::free_balance(&1), 1000 - 50);
+        assert_eq!(<fees::Module<Test>>::sponsorship(5).unwrap().remaining, Some(50));
     });
 }
 
 #[test]
-fn commit_fail_anchor_exists() {
+fn extend_anchor() {
     new_test_ext().execute_with(|| {
+        let day = |n| common::MS_PER_DAY * n + 1;
         let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
         let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
-        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&1);
 
-        // happy
+        // unknown anchor
+        assert_err!(
+            Anchor::extend_anchor(Origin::signed(1), anchor_id, day(4)),
+            Error::<Test>::AnchorNotFound
+        );
+
+        // stored until day 2
         assert_ok!(Anchor::commit(
             Origin::signed(1),
             pre_image,
             doc_root,
             <Test as frame_system::Trait>::Hashing::hash_of(&0),
-            common::MS_PER_DAY + 1
+            day(1)
         ));
-        // asserting that the stored anchor id is what we sent the pre-image for
-        let a = Anchor::get_anchor_by_id(anchor_id).unwrap();
-        assert_eq!(a.id, anchor_id);
-        assert_eq!(a.doc_root, doc_root);
+        let anchor = Anchor::get_anchor_by_id(anchor_id).unwrap().unwrap();
 
+        // 10 per day of storage, default author of the test runtime is account 0
+        assert_ok!(<fees::Module<Test>>::set_fee(Origin::root(), Anchor::fee_key(), 10));
+        <pallet_balances::Module<Test>>::make_free_balance_be(&1, 1000);
+        <pallet_balances::Module<Test>>::make_free_balance_be(&0, 1);
+
+        // reject unsigned
         assert_err!(
-            Anchor::commit(
-                Origin::signed(1),
-                pre_image,
-                doc_root,
-                <Test as frame_system::Trait>::Hashing::hash_of(&0),
-                common::MS_PER_DAY + 1
-            ),
-            "Anchor already exists"
+            Anchor::extend_anchor(Origin::none(), anchor_id, day(4)),
+            BadOrigin
         );
 
-        // different acc
+        // extend until day 5, only the 3 extra days are charged
+        assert_ok!(Anchor::extend_anchor(Origin::signed(1), anchor_id, day(4)));
+        assert_eq!(<pallet_balances::Module<Test>>::free_balance(&1), 1000 - 30);
+        assert_eq!(<pallet_balances::Module<Test>>::free_balance(&0), 1 + 27);
+        assert_eq!(
+            <pallet_balances::Module<Test>>::free_balance(&Anchor::eviction_bounty_pool_account()),
+            3
+        );
+        assert_eq!(
+            last_event(),
+            TestEvent::anchor(RawEvent::AnchorExtended(anchor_id, 2, 5))
+        );
+        assert_eq!(Anchor::get_anchor_evict_date(anchor_id), 5);
+        assert_eq!(Anchor::get_anchor_by_id(anchor_id).unwrap().unwrap(), anchor);
+        assert!(child::get_raw(&Anchor::anchor_child_info(2), anchor_id.as_ref()).is_none());
+
+        // the date must be later than the current one
         assert_err!(
-            Anchor::commit(
-                Origin::signed(2),
-                pre_image,
-                doc_root,
-                <Test as frame_system::Trait>::Hashing::hash_of(&0),
-                common::MS_PER_DAY + 1
-            ),
-            "Anchor already exists"
+            Anchor::extend_anchor(Origin::signed(1), anchor_id, day(4)),
+            Error::<Test>::EvictionDateNotExtended
+        );
+        assert_err!(
+            Anchor::extend_anchor(Origin::signed(1), anchor_id, day(2)),
+            Error::<Test>::EvictionDateNotExtended
+        );
+
+        // the date must not be after the maximum storage date
+        assert_err!(
+            Anchor::extend_anchor(Origin::signed(1), anchor_id, day(StorageMaxDays::get() as u64)),
+            Error::<Test>::EvictionDateTooBig
         );
+
+        // the anchor survives the eviction of its original day
+        <pallet_timestamp::Module<Test>>::set_timestamp(day(4));
+        assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
+        assert_eq!(Anchor::get_anchor_by_id(anchor_id).unwrap().unwrap(), anchor);
+
+        <pallet_timestamp::Module<Test>>::set_timestamp(day(5));
+        assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
+        assert!(Anchor::get_anchor_by_id(anchor_id).unwrap().is_none());
     });
 }
 
 #[test]
-fn basic_pre_commit_commit() {
+fn extend_anchor_at_least_a_day_from_now() {
     new_test_ext().execute_with(|| {
+        let day = |n| common::MS_PER_DAY * n + 1;
         let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
         let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
-        let random_doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
-        let (doc_root, signing_root, proof) = Test::test_document_hashes();
 
-        // happy
-        assert_ok!(Anchor::pre_commit(
+        // stored until day 4
+        assert_ok!(Anchor::commit(
             Origin::signed(1),
-            anchor_id,
-            signing_root
+            pre_image,
+            <Test as frame_system::Trait>::Hashing::hash_of(&1),
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            day(3)
         ));
 
-        // wrong doc root
+        assert_ok!(<fees::Module<Test>>::set_fee(Origin::root(), Anchor::fee_key(), 10));
+        <pallet_balances::Module<Test>>::make_free_balance_be(&1, 1000);
+
+        // a later date that is not more than a day from now is rejected, as for commit
+        <pallet_timestamp::Module<Test>>::set_timestamp(day(3));
         assert_err!(
-            Anchor::commit(
-                Origin::signed(1),
-                pre_image,
-                random_doc_root,
-                proof,
-                common::MS_PER_DAY + 1
-            ),
-            "Pre-commit proof not valid"
+            Anchor::extend_anchor(Origin::signed(1), anchor_id, day(4)),
+            Error::<Test>::EvictionDateTooEarly
         );
 
-        // happy
-        assert_ok!(Anchor::commit(
-            Origin::signed(1),
-            pre_image,
-            doc_root,
-            proof,
-            common::MS_PER_DAY + 1
-        ));
-        // asserting that the stored anchor id is what we sent the pre-image for
-        let a = Anchor::get_anchor_by_id(anchor_id).unwrap();
-        assert_eq!(a.id, anchor_id);
-        assert_eq!(a.doc_root, doc_root);
+        assert_ok!(Anchor::extend_anchor(Origin::signed(1), anchor_id, day(4) + 1));
+        assert_eq!(Anchor::get_anchor_evict_date(anchor_id), 5);
     });
 }
 
 #[test]
-fn pre_commit_expired_when_anchoring() {
-    new_test_ext().execute_with(|| {
+fn commit_fail_missing_fee_key() {
+    // no anchoring fee set up on genesis
+    let t = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+    sp_io::TestExternalities::new(t).execute_with(|| {
         let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
-        let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
-        let (doc_root, signing_root, proof) = Test::test_document_hashes();
-
-        // happy
-        assert_ok!(Anchor::pre_commit(
-            Origin::signed(1),
-            anchor_id,
-            signing_root
-        ));
-        // expire the pre-commit
-        System::set_block_number(Anchor::pre_commit_expiration_duration_blocks() + 2);
+        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        assert_err!(
+            Anchor::commit(
+                Origin::signed(1),
+                pre_image,
+                doc_root,
+                <Test as frame_system::Trait>::Hashing::hash_of(&0),
+                common::MS_PER_DAY + 1
+            ),
+            Error::<Test>::AnchorFeeNotFound
+        );
 
-        // happy from a different account
-        assert_ok!(Anchor::commit(
-            Origin::signed(2),
-            pre_image,
-            doc_root,
-            proof,
-            common::MS_PER_DAY + 1
-        ));
-        // asserting that the stored anchor id is what we sent the pre-image for
-        let a = Anchor::get_anchor_by_id(anchor_id).unwrap();
-        assert_eq!(a.id, anchor_id);
-        assert_eq!(a.doc_root, doc_root);
+        let commits = vec![batch_commit(0, doc_root, doc_root, common::MS_PER_DAY + 1)];
+        assert_err!(
+            Anchor::commit_batch(Origin::signed(1), commits, true),
+            Error::<Test>::AnchorFeeNotFound
+        );
+        assert_eq!(Anchor::get_latest_anchor_index(), 0);
     });
 }
 
 #[test]
-fn pre_commit_commit_fail_from_another_acc() {
+fn corrupt_anchor_data_in_child_trie() {
     new_test_ext().execute_with(|| {
         let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
         let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
-        let (doc_root, signing_root, proof) = Test::test_document_hashes();
+        let doc_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
 
-        // happy
-        assert_ok!(Anchor::pre_commit(
-            Origin::signed(1),
-            anchor_id,
-            signing_root
-        ));
+        // store an undecodable anchor on the eviction day of the anchor id
+        let day: u32 = 2;
+        <AnchorEvictDates<Test>>::insert(&anchor_id, &day);
+        let child_info =
+            common::generate_child_storage_key(&Anchor::anchor_storage_key(&day.encode()));
+        child::put_raw(&child_info, anchor_id.as_ref(), &[1, 2, 3]);
 
-        // fail from a different account
+        assert_err!(
+            Anchor::get_anchor_by_id(anchor_id),
+            Error::<Test>::AnchorDataCorrupted
+        );
+        assert_err!(
+            Anchor::pre_commit(Origin::signed(1), anchor_id, doc_root),
+            Error::<Test>::AnchorDataCorrupted
+        );
         assert_err!(
             Anchor::commit(
-                Origin::signed(2),
+                Origin::signed(1),
                 pre_image,
                 doc_root,
-                proof,
-                common::MS_PER_DAY + 1
+                <Test as frame_system::Trait>::Hashing::hash_of(&0),
+                common::MS_PER_DAY * 3 + 1
             ),
-            "Pre-commit owned by someone else"
+            Error::<Test>::AnchorDataCorrupted
         );
     });
 }
@@ -514,7 +1327,8 @@ fn pre_commit_commit_bucket_gets_determined_correctly() {
     new_test_ext().execute_with(|| {
         let current_block: <Test as frame_system::Trait>::BlockNumber = 1;
         let expected_evict_bucket: <Test as frame_system::Trait>::BlockNumber =
-            PRE_COMMIT_EXPIRATION_DURATION_BLOCKS * PRE_COMMIT_EVICTION_BUCKET_MULTIPLIER;
+            PreCommitExpirationDurationBlocks::get()
+                * PreCommitEvictionBucketMultiplier::get() as u64;
         assert_eq!(
             Ok(expected_evict_bucket),
             Anchor::determine_pre_commit_eviction_bucket(current_block)
@@ -742,7 +1556,7 @@ fn pre_commit_and_then_evict() {
                 Origin::signed(1),
                 Anchor::determine_pre_commit_eviction_bucket(block_height_0).unwrap()
             ),
-            "eviction only possible for bucket expiring < current block height"
+            Error::<Test>::EvictionBucketNotExpired
         );
 
         // test that eviction works after expiration time
@@ -779,7 +1593,7 @@ fn pre_commit_at_7999_and_then_evict_before_expire_and_collaborator_succeed_comm
         let (doc_root, signing_root, proof) = Test::test_document_hashes();
         // use as a start block a block that is before an eviction bucket boundary
         let start_block = Anchor::pre_commit_expiration_duration_blocks()
-            * PRE_COMMIT_EVICTION_BUCKET_MULTIPLIER
+            * PreCommitEvictionBucketMultiplier::get() as u64
             * 2
             - 1;
         // expected expiry block of pre-commit
@@ -821,7 +1635,7 @@ fn pre_commit_at_7999_and_then_evict_before_expire_and_collaborator_succeed_comm
                 proof,
                 common::MS_PER_DAY + 1
             ),
-            "Pre-commit owned by someone else"
+            Error::<Test>::NotOwnerOfPreCommit
         );
     });
 }
@@ -835,7 +1649,7 @@ fn pre_commit_and_then_evict_larger_than_max_evict() {
         let signing_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
 
         System::set_block_number(block_height_0);
-        for idx in 0..MAX_LOOP_IN_TX + 6 {
+        for idx in 0..MaxLoopInTx::get() + 6 {
             assert_ok!(Anchor::pre_commit(
                 Origin::signed(1),
                 <Test as frame_system::Trait>::Hashing::hash_of(&idx),
@@ -866,7 +1680,7 @@ fn anchor_evict_single_anchor_per_day_1000_days() {
         let (doc_root, _signing_root, proof) = Test::test_document_hashes();
         let mut anchors = vec![];
         let verify_anchor_eviction = |day: usize, anchors: &Vec<H256>| {
-            assert!(Anchor::get_anchor_by_id(anchors[day - 2]).is_none());
+            assert!(Anchor::get_anchor_by_id(anchors[day - 2]).unwrap().is_none());
             assert_eq!(Anchor::get_latest_evicted_anchor_index(), (day - 1) as u64);
             assert_eq!(
                 Anchor::get_anchor_id_by_index((day - 1) as u64),
@@ -876,7 +1690,7 @@ fn anchor_evict_single_anchor_per_day_1000_days() {
             assert_eq!(Anchor::get_anchor_evict_date(anchors[day - 2]), 0);
         };
         let verify_next_anchor_after_eviction = |day: usize, anchors: &Vec<H256>| {
-            assert!(Anchor::get_anchor_by_id(anchors[day - 1]).is_some());
+            assert!(Anchor::get_anchor_by_id(anchors[day - 1]).unwrap().is_some());
             assert_eq!(Anchor::get_anchor_id_by_index(day as u64), anchors[day - 1]);
             assert_eq!(
                 Anchor::get_anchor_evict_date(anchors[day - 1]),
@@ -899,7 +1713,7 @@ fn anchor_evict_single_anchor_per_day_1000_days() {
                 day(i + 1)
             ));
 
-            assert!(Anchor::get_anchor_by_id(anchor_id).is_some());
+            assert!(Anchor::get_anchor_by_id(anchor_id).unwrap().is_some());
             assert_eq!(Anchor::get_latest_anchor_index(), i + 1);
             assert_eq!(Anchor::get_anchor_id_by_index(i + 1), anchor_id);
             assert_eq!(Anchor::get_latest_evicted_anchor_index(), 0);
@@ -910,7 +1724,7 @@ fn anchor_evict_single_anchor_per_day_1000_days() {
 
         // eviction on day 3
         <pallet_timestamp::Module<Test>>::set_timestamp(day(2));
-        assert!(Anchor::get_anchor_by_id(anchors[0]).is_some());
+        assert!(Anchor::get_anchor_by_id(anchors[0]).unwrap().is_some());
         assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
         verify_anchor_eviction(2, &anchors);
         assert_eq!(
@@ -926,7 +1740,7 @@ fn anchor_evict_single_anchor_per_day_1000_days() {
         // do the same as above for next 99 days without child trie root verification
         for i in 3..102 {
             <pallet_timestamp::Module<Test>>::set_timestamp(day(i as u64));
-            assert!(Anchor::get_anchor_by_id(anchors[i - 2]).is_some());
+            assert!(Anchor::get_anchor_by_id(anchors[i - 2]).unwrap().is_some());
 
             // evict
             assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
@@ -939,17 +1753,17 @@ fn anchor_evict_single_anchor_per_day_1000_days() {
         // eviction on day 602, i.e 501 anchors to be removed one anchor
         // per day from the last eviction on day 102
         <pallet_timestamp::Module<Test>>::set_timestamp(day(602));
-        assert!(Anchor::get_anchor_by_id(anchors[600]).is_some());
+        assert!(Anchor::get_anchor_by_id(anchors[600]).unwrap().is_some());
         // evict
         assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
         // verify anchor data has been removed until 520th anchor
         for i in 102..602 {
-            assert!(Anchor::get_anchor_by_id(anchors[i - 2]).is_none());
+            assert!(Anchor::get_anchor_by_id(anchors[i - 2]).unwrap().is_none());
             assert!(Anchor::get_evicted_anchor_root_by_day(i as u32) != [0; 32]);
         }
 
-        assert!(Anchor::get_anchor_by_id(anchors[600]).is_none());
-        assert!(Anchor::get_anchor_by_id(anchors[601]).is_some());
+        assert!(Anchor::get_anchor_by_id(anchors[600]).unwrap().is_none());
+        assert!(Anchor::get_anchor_by_id(anchors[601]).unwrap().is_some());
 
         // verify that 601st anchors` indexes are left still because of 500 limit while
         // 600th anchors` indexes have been removed
@@ -970,15 +1784,316 @@ fn anchor_evict_single_anchor_per_day_1000_days() {
 
         // remove remaining anchors
         <pallet_timestamp::Module<Test>>::set_timestamp(day(1001));
-        assert!(Anchor::get_anchor_by_id(anchors[999]).is_some());
+        assert!(Anchor::get_anchor_by_id(anchors[999]).unwrap().is_some());
         assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
-        assert!(Anchor::get_anchor_by_id(anchors[999]).is_none());
+        assert!(Anchor::get_anchor_by_id(anchors[999]).unwrap().is_none());
         assert_eq!(Anchor::get_latest_evicted_anchor_index(), 1000);
         assert_eq!(Anchor::get_anchor_id_by_index(1000), H256([0; 32]));
         assert_eq!(Anchor::get_anchor_evict_date(anchors[999]), 0);
     });
 }
 
+#[test]
+fn eviction_bounty() {
+    new_test_ext().execute_with(|| {
+        let day = |n| common::MS_PER_DAY * n + 1;
+        let (doc_root, signing_root, proof) = Test::test_document_hashes();
+        let pool = Anchor::eviction_bounty_pool_account();
+        let pre_commit_bounty = PRE_COMMIT_EVICTION_BOUNTY as u64;
+        let child_trie_bounty = ANCHOR_CHILD_TRIE_EVICTION_BOUNTY as u64;
+        let balance_of = |who| <pallet_balances::Module<Test>>::free_balance(&who);
+        <pallet_balances::Module<Test>>::make_free_balance_be(&pool, 100 * child_trie_bounty);
+        <pallet_balances::Module<Test>>::make_free_balance_be(&2, 1);
+
+        for i in 0..3 {
+            assert_ok!(Anchor::pre_commit(
+                Origin::signed(1),
+                <Test as frame_system::Trait>::Hashing::hash_of(&i),
+                signing_root
+            ));
+        }
+        let bucket = Anchor::determine_pre_commit_eviction_bucket(1).unwrap();
+        System::set_block_number(bucket);
+
+        // a bounty for each evicted pre-commit
+        assert_ok!(Anchor::evict_pre_commits(Origin::signed(2), bucket));
+        assert_eq!(balance_of(2), 1 + 3 * pre_commit_bounty);
+        assert_eq!(
+            last_event(),
+            TestEvent::anchor(RawEvent::EvictionBountyPaid(2, 3 * pre_commit_bounty))
+        );
+
+        // nothing left to evict, nothing paid
+        assert_ok!(Anchor::evict_pre_commits(Origin::signed(2), bucket));
+        assert_eq!(balance_of(2), 1 + 3 * pre_commit_bounty);
+
+        // a bounty for each evicted child trie
+        assert_ok!(Anchor::commit(
+            Origin::signed(1),
+            <Test as frame_system::Trait>::Hashing::hash_of(&10),
+            doc_root,
+            proof,
+            day(1)
+        ));
+        <pallet_timestamp::Module<Test>>::set_timestamp(day(3));
+        assert_ok!(Anchor::evict_anchors(Origin::signed(2)));
+        assert_eq!(
+            balance_of(2),
+            1 + 3 * pre_commit_bounty + 3 * child_trie_bounty
+        );
+
+        // nothing left to evict, nothing paid
+        assert_ok!(Anchor::evict_anchors(Origin::signed(2)));
+        assert_eq!(
+            balance_of(2),
+            1 + 3 * pre_commit_bounty + 3 * child_trie_bounty
+        );
+
+        // never more than what is left in the pool
+        <pallet_balances::Module<Test>>::make_free_balance_be(&pool, child_trie_bounty);
+        <pallet_timestamp::Module<Test>>::set_timestamp(day(10));
+        assert_ok!(Anchor::evict_anchors(Origin::signed(2)));
+        assert_eq!(
+            balance_of(2),
+            1 + 3 * pre_commit_bounty + 4 * child_trie_bounty
+        );
+        assert_eq!(balance_of(pool), 0);
+
+        // an empty pool pays nothing
+        <pallet_timestamp::Module<Test>>::set_timestamp(day(12));
+        assert_ok!(Anchor::evict_anchors(Origin::signed(2)));
+        assert_eq!(
+            balance_of(2),
+            1 + 3 * pre_commit_bounty + 4 * child_trie_bounty
+        );
+    });
+}
+
+#[test]
+fn on_initialize_evictions_keep_up() {
+    new_test_ext().execute_with(|| {
+        let day = |n| common::MS_PER_DAY * n + 1;
+        let blocks_per_day = 1_000;
+        let (doc_root, signing_root, proof) = Test::test_document_hashes();
+
+        let pre_commits: Vec<_> = (100..105)
+            .map(|i| <Test as frame_system::Trait>::Hashing::hash_of(&i))
+            .collect();
+        for id in pre_commits.iter() {
+            assert_ok!(Anchor::pre_commit(Origin::signed(1), *id, signing_root));
+        }
+
+        // anchor n is stored until day n + 1
+        let anchors: Vec<_> = (1..=10)
+            .map(|n| {
+                let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&n);
+                assert_ok!(Anchor::commit(
+                    Origin::signed(1),
+                    pre_image,
+                    doc_root,
+                    proof,
+                    day(n)
+                ));
+                (n, (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash))
+            })
+            .collect();
+
+        for d in 1..=15 {
+            for b in 0..3 {
+                let block = d * blocks_per_day + b;
+                System::set_block_number(block);
+                <pallet_timestamp::Module<Test>>::set_timestamp(day(d));
+                Anchor::on_initialize(block);
+            }
+
+            // today is d + 1, so all anchors stored until day d are gone
+            assert_eq!(Anchor::get_latest_evicted_date(), d as u32);
+            for (n, anchor_id) in anchors.iter() {
+                assert_eq!(
+                    Anchor::get_anchor_by_id(*anchor_id).unwrap().is_none(),
+                    n + 1 <= d
+                );
+            }
+
+            // pre-commits expire at block 801 and are evicted with the bucket at block 4000
+            for id in pre_commits.iter() {
+                assert_eq!(<PreCommits<Test>>::contains_key(id), d < 4);
+            }
+        }
+
+        assert_eq!(Anchor::get_pre_commits_count_in_evict_bucket(4000), 0);
+        assert_eq!(Anchor::get_next_pre_commit_eviction_bucket(), 16000);
+        assert_eq!(Anchor::get_latest_evicted_anchor_index(), 10);
+    });
+}
+
+#[test]
+fn on_initialize_evictions_are_bounded() {
+    new_test_ext().execute_with(|| {
+        // nothing was evicted for 20 days
+        <pallet_timestamp::Module<Test>>::set_timestamp(common::MS_PER_DAY * 20 + 1);
+
+        // the weight budget of the test runtime only allows a single day per block
+        for block in 1..=20 {
+            System::set_block_number(block);
+            Anchor::on_initialize(block);
+            assert_eq!(Anchor::get_latest_evicted_date(), block as u32);
+            assert_eq!(
+                last_event(),
+                TestEvent::anchor(RawEvent::AnchorsEvicted(block as u32, block as u32, 1))
+            );
+        }
+
+        // the manual extrinsic still evicts everything that is left at once
+        <pallet_timestamp::Module<Test>>::set_timestamp(common::MS_PER_DAY * 30 + 1);
+        assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
+        assert_eq!(Anchor::get_latest_evicted_date(), 30);
+    });
+}
+
+#[test]
+fn on_initialize_evicts_anchors_of_a_day_over_several_blocks() {
+    new_test_ext().execute_with(|| {
+        let day = |n| common::MS_PER_DAY * n + 1;
+        let (doc_root, _signing_root, proof) = Test::test_document_hashes();
+
+        // three anchors stored until day 1
+        for i in 0..3 {
+            assert_ok!(Anchor::commit(
+                Origin::signed(1),
+                <Test as frame_system::Trait>::Hashing::hash_of(&i),
+                doc_root,
+                proof,
+                day(1)
+            ));
+        }
+        let root = child::root(&Anchor::anchor_child_info(1));
+        <pallet_timestamp::Module<Test>>::set_timestamp(day(3));
+
+        // the weight budget of the test runtime only allows a single anchor per block, the root
+        // of the whole child trie is stored with the first one
+        for block in 1..=2 {
+            System::set_block_number(block);
+            Anchor::on_initialize(block);
+            assert_eq!(Anchor::get_latest_evicted_date(), 0);
+            assert_eq!(Anchor::get_evicted_anchor_root_by_day(1), root);
+            assert_eq!(Anchor::get_evicted_anchor_block_by_day(1), Some(1));
+        }
+
+        System::set_block_number(3);
+        Anchor::on_initialize(3);
+        assert_eq!(Anchor::get_latest_evicted_date(), 1);
+        assert_eq!(Anchor::get_anchors_by_evict_date(1, None, 10).len(), 0);
+
+        System::set_block_number(4);
+        Anchor::on_initialize(4);
+        assert_eq!(Anchor::get_latest_evicted_date(), 2);
+    });
+}
+
+#[test]
+fn list_anchors() {
+    new_test_ext().execute_with(|| {
+        let day = |n| common::MS_PER_DAY * n + 1;
+        let (doc_root, _signing_root, proof) = Test::test_document_hashes();
+
+        // three anchors evicted after day 2 and two after day 3
+        let mut anchor_ids = vec![];
+        for i in 0..5 {
+            let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&i);
+            assert_ok!(Anchor::commit(
+                Origin::signed(1),
+                pre_image,
+                doc_root,
+                proof,
+                day(if i < 3 { 1 } else { 2 })
+            ));
+            anchor_ids.push((pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash));
+        }
+        assert_eq!(
+            Anchor::get_anchor_counts(),
+            AnchorCounts {
+                latest_index: 5,
+                latest_evicted_index: 0
+            }
+        );
+
+        // by index range, bounded by the latest index
+        let anchors = Anchor::get_anchors_by_index_range(2, 2);
+        assert_eq!(
+            anchors.iter().map(|a| a.anchor.id).collect::<Vec<_>>(),
+            anchor_ids[1..3].to_vec()
+        );
+        assert!(anchors.iter().all(|a| a.evict_date == 2));
+        assert_eq!(Anchor::get_anchors_by_index_range(0, 100).len(), 5);
+        assert_eq!(
+            Anchor::get_anchors_by_index_range(0, 2)
+                .iter()
+                .map(|a| a.anchor.id)
+                .collect::<Vec<_>>(),
+            anchor_ids[0..2].to_vec()
+        );
+        assert_eq!(Anchor::get_anchors_by_index_range(5, 100).len(), 1);
+        assert!(Anchor::get_anchors_by_index_range(6, 100).is_empty());
+
+        // by eviction date, paged by anchor id
+        let mut expected = anchor_ids[3..].to_vec();
+        expected.sort();
+        let first_page = Anchor::get_anchors_by_evict_date(3, None, 1);
+        assert_eq!(first_page.len(), 1);
+        assert_eq!(first_page[0].anchor.id, expected[0]);
+        assert_eq!(first_page[0].evict_date, 3);
+        let second_page = Anchor::get_anchors_by_evict_date(3, Some(expected[0]), 10);
+        assert_eq!(
+            second_page.iter().map(|a| a.anchor.id).collect::<Vec<_>>(),
+            expected[1..].to_vec()
+        );
+        assert_eq!(Anchor::get_anchors_by_evict_date(2, None, 10).len(), 3);
+        assert!(Anchor::get_anchors_by_evict_date(4, None, 10).is_empty());
+
+        // evicted anchors are not listed anymore
+        <pallet_timestamp::Module<Test>>::set_timestamp(day(2));
+        assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
+        assert_eq!(
+            Anchor::get_anchor_counts(),
+            AnchorCounts {
+                latest_index: 5,
+                latest_evicted_index: 3
+            }
+        );
+        assert_eq!(
+            Anchor::get_anchors_by_index_range(1, 100)
+                .iter()
+                .map(|a| a.anchor.id)
+                .collect::<Vec<_>>(),
+            anchor_ids[3..].to_vec()
+        );
+        assert!(Anchor::get_anchors_by_evict_date(2, None, 10).is_empty());
+    });
+}
+
+#[test]
+fn anchor_evict_date_key() {
+    new_test_ext().execute_with(|| {
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+        let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        let (doc_root, _signing_root, proof) = Test::test_document_hashes();
+        assert_ok!(Anchor::commit(
+            Origin::signed(1),
+            pre_image,
+            doc_root,
+            proof,
+            common::MS_PER_DAY + 1
+        ));
+
+        // the raw storage at the key leads to the child trie of the anchor
+        let evict_date = sp_io::storage::get(&Anchor::anchor_evict_date_key(anchor_id))
+            .map(|data| u32::decode(&mut &*data).unwrap());
+        assert_eq!(evict_date, Some(2));
+        assert!(child::get_raw(&Anchor::anchor_child_info(2), anchor_id.as_ref()).is_some());
+    });
+}
+
 #[test]
 fn test_remove_anchor_indexes() {
     new_test_ext().execute_with(|| {
@@ -1003,24 +2118,24 @@ fn test_remove_anchor_indexes() {
         }
         assert_eq!(Anchor::get_latest_anchor_index(), 2000);
 
-        // first MAX_LOOP_IN_TX items
+        // first MaxLoopInTx items
         let removed = Anchor::remove_anchor_indexes(2);
-        assert_eq!(removed as u64, MAX_LOOP_IN_TX);
+        assert_eq!(removed as u64, MaxLoopInTx::get());
         assert_eq!(Anchor::get_latest_evicted_anchor_index(), 500);
 
-        // second MAX_LOOP_IN_TX items
+        // second MaxLoopInTx items
         let removed = Anchor::remove_anchor_indexes(2);
-        assert_eq!(removed as u64, MAX_LOOP_IN_TX);
+        assert_eq!(removed as u64, MaxLoopInTx::get());
         assert_eq!(Anchor::get_latest_evicted_anchor_index(), 1000);
 
-        // third MAX_LOOP_IN_TX items
+        // third MaxLoopInTx items
         let removed = Anchor::remove_anchor_indexes(2);
-        assert_eq!(removed as u64, MAX_LOOP_IN_TX);
+        assert_eq!(removed as u64, MaxLoopInTx::get());
         assert_eq!(Anchor::get_latest_evicted_anchor_index(), 1500);
 
-        // fourth MAX_LOOP_IN_TX items
+        // fourth MaxLoopInTx items
         let removed = Anchor::remove_anchor_indexes(2);
-        assert_eq!(removed as u64, MAX_LOOP_IN_TX);
+        assert_eq!(removed as u64, MaxLoopInTx::get());
         assert_eq!(Anchor::get_latest_evicted_anchor_index(), 2000);
 
         // all done
@@ -1057,9 +2172,9 @@ fn test_same_day_1001_anchors() {
 
         // first 500
         <pallet_timestamp::Module<Test>>::set_timestamp(day(2));
-        assert!(Anchor::get_anchor_by_id(anchors[999]).is_some());
+        assert!(Anchor::get_anchor_by_id(anchors[999]).unwrap().is_some());
         assert_ok!(Anchor::evict_anchors(Origin::signed(1)));
-        assert!(Anchor::get_anchor_by_id(anchors[999]).is_none());
+        assert!(Anchor::get_anchor_by_id(anchors[999]).unwrap().is_none());
         assert_eq!(Anchor::get_latest_evicted_anchor_index(), 500);
         assert_eq!(Anchor::get_anchor_id_by_index(500), H256([0; 32]));
         assert_eq!(Anchor::get_anchor_evict_date(anchors[499]), 0);
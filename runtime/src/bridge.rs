@@ -5,14 +5,16 @@ use codec::{Decode, Encode};
 use unique_assets::traits::Unique;
 use crate::va_registry::types::{RegistryId, AssetId, TokenId};
 use crate::{fees, constants::currency};
-use frame_support::traits::{Currency, ExistenceRequirement::AllowDeath, Get};
+use frame_support::traits::{
+    Currency, ExistenceRequirement::{AllowDeath, KeepAlive}, Get, WithdrawReason,
+};
 use frame_support::{
     decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResult, ensure,
     traits::EnsureOrigin,
 };
 use frame_system::{self as system, ensure_signed, ensure_root};
 use sp_core::U256;
-use sp_runtime::traits::SaturatedConversion;
+use sp_runtime::traits::{Hash, SaturatedConversion};
 use sp_std::prelude::*;
 
 /// Abstract identifer of an asset, for a common vocabulary across chains.
@@ -27,8 +29,10 @@ pub struct Address(pub Bytes32);
 /// Length of an [Address] type
 const ADDR_LEN: usize = 32;
 
-/// Additional Fee charged when moving NFTs to target chains (RAD)
-const NFT_TOKEN_FEE: u128 = 20 * currency::CFG;
+/// Initial price of the fee charged when moving NFTs to target chains (RAD), burnt unless its
+/// distribution is changed. The price can be changed with `fees::set_fee`, see
+/// `Module::nft_fee_key`.
+pub const NFT_TOKEN_FEE: u128 = 20 * currency::CFG;
 
 type Bytes32 = [u8; ADDR_LEN];
 
@@ -131,7 +135,12 @@ decl_module! {
             ensure!(<chainbridge::Module<T>>::chain_whitelisted(dest_id), Error::<T>::InvalidTransfer);
 
             // Burn additional fees
-            <fees::Module<T>>::burn_fee(&source, token_fee)?;
+            let _ = <pallet_balances::Module<T> as Currency<_>>::withdraw(
+                &source,
+                token_fee,
+                WithdrawReason::Fee.into(),
+                KeepAlive,
+            )?;
 
             let bridge_id = <chainbridge::Module<T>>::account_id();
             T::Currency::transfer(&source, &bridge_id, amount.into(), AllowDeath)?;
@@ -159,9 +168,8 @@ decl_module! {
             let resource_id = <bridge_mapping::Module<T>>::name_of(reg)
                 .ok_or(Error::<T>::ResourceIdDoesNotExist)?;
 
-            // Burn additional fees
-            let nft_fee: T::Balance = NFT_TOKEN_FEE.saturated_into();
-            <fees::Module<T>>::burn_fee(&source, nft_fee)?;
+            // Pay additional fees
+            <fees::Module<T>>::pay_fee(source.clone(), Self::nft_fee_key())?;
 
             // Lock asset by transferring to bridge account
             let bridge_id = <chainbridge::Module<T>>::account_id();
@@ -257,6 +265,25 @@ impl<T: Trait> Module<T> {
 			.or_else(ensure_root)?;
 		Ok(())
 	}
+
+    /// Key of the fee charged by `transfer_asset`.
+    pub fn nft_fee_key() -> T::Hash {
+        T::Hashing::hash_of(&(b"bridge", b"transfer_asset"))
+    }
+
+    /// Fee keys of this module to register in the fees module.
+    pub fn fee_key_registrations() -> Vec<fees::FeeKeyRegistration<T::Hash, T::Balance>> {
+        vec![fees::FeeKeyRegistration {
+            key: Self::nft_fee_key(),
+            info: fees::FeeKeyInfo {
+                name: b"bridge_nft_transfer".to_vec(),
+                unit: fees::FeeUnit::PerCall,
+                pallet: b"bridge".to_vec(),
+            },
+            initial_price: Some(NFT_TOKEN_FEE.saturated_into()),
+            initial_distribution: Some(fees::FeeDistribution::burn_all()),
+        }]
+    }
 }
 
 #[cfg(test)]
@@ -274,7 +301,7 @@ mod tests{
 		traits::{AccountIdConversion, BlakeTwo256, Hash, Block as BlockT, IdentityLookup}, ModuleId, Perbill,
 	};
 	use crate::bridge as pallet_bridge;
-    use crate::{nft, va_registry as registry};
+    use crate::{anchor, nft, va_registry as registry};
 
 	pub use pallet_balances as balances;
 	use sp_runtime::DispatchError::BadOrigin;
@@ -346,9 +373,19 @@ mod tests{
 		type ProposalLifetime = ProposalLifetime;
 	}
 
+	parameter_types! {
+		pub FeeKeyRegistrations: Vec<fees::FeeKeyRegistration<H256, u128>> = PalletBridge::fee_key_registrations();
+		pub SponsoredFeeKeys: Vec<H256> = vec![Anchor::fee_key()];
+	}
+
 	impl fees::Trait for Test {
 		type Event = Event;
 		type FeeChangeOrigin = frame_system::EnsureRoot<u64>;
+		type EvictionBountyPoolAccount = ();
+		type DefaultFeeDistribution = ();
+		type FeeKeyRegistrations = FeeKeyRegistrations;
+		type MinFeeChangeNotice = ();
+		type SponsoredFeeKeys = SponsoredFeeKeys;
 	}
 
 	impl pallet_authorship::Trait for Test {
@@ -369,12 +406,41 @@ mod tests{
         type AdminOrigin = frame_system::EnsureRoot<Self::AccountId>;
     }
 
+    parameter_types! {
+        pub const MaxProofs: u32 = 4;
+        pub const MaxProofHashes: u32 = 4;
+        pub const MaxProofFieldLength: u32 = 64;
+    }
+
     // So that nfts can be minted
     impl registry::Trait for Test {
         type Event = Event;
+        type WeightInfo = ();
+        type MaxProofs = MaxProofs;
+        type MaxProofHashes = MaxProofHashes;
+        type MaxProofFieldLength = MaxProofFieldLength;
+    }
+
+    parameter_types! {
+        pub const PreCommitExpirationDurationBlocks: u64 = 800;
+        pub const PreCommitEvictionBucketMultiplier: u32 = 5;
+        pub const MaxLoopInTx: u64 = 500;
+        pub const StorageMaxDays: u32 = 376200;
+        pub const MaxEvictedAnchorProofNodes: u32 = 16;
+        pub const MaxEvictedAnchorProofSize: u32 = 16 * 1024;
     }
 
-    impl crate::anchor::Trait for Test {}
+    impl crate::anchor::Trait for Test {
+        type Event = Event;
+        type WeightInfo = ();
+        type PreCommitExpirationDurationBlocks = PreCommitExpirationDurationBlocks;
+        type PreCommitEvictionBucketMultiplier = PreCommitEvictionBucketMultiplier;
+        type MaxLoopInTx = MaxLoopInTx;
+        type StorageMaxDays = StorageMaxDays;
+        type MaxEvictedAnchorProofNodes = MaxEvictedAnchorProofNodes;
+        type MaxEvictedAnchorProofSize = MaxEvictedAnchorProofSize;
+        type AdminOrigin = frame_system::EnsureRoot<Self::AccountId>;
+    }
 
     impl pallet_timestamp::Trait for Test {
         type Moment = u64;
@@ -415,6 +481,7 @@ mod tests{
 			Fees: fees::{Module, Call, Event<T>},
             Nft: nft::{Module, Event<T>},
             Registry: registry::{Module, Call, Event<T>},
+            Anchor: anchor::{Module, Call, Event<T>},
 		}
 	);
 
@@ -599,7 +666,10 @@ mod tests{
                       registry::types::MintInfo {
                           anchor_id: anchor_id,
                           proofs: proofs,
+                          multi_proof: None,
+                          document_layout: crate::proofs::DOCUMENT_LAYOUT_V1,
                           static_hashes: static_hashes,
+                          evicted_anchor: None,
                       }));
 
         // Register resource with chainbridge
@@ -701,6 +771,40 @@ mod tests{
         })
     }
 
+    #[test]
+    fn transfer_nonfungible_asset_is_not_sponsored() {
+        new_test_ext().execute_with(|| {
+            let dest_chain = 0;
+            let resource_id = NativeTokenId::get();
+            let owner = RELAYER_A;
+            let sponsor = RELAYER_C;
+            let token_id = U256::one();
+            let balance_of = |who| <pallet_balances::Module<Test>>::free_balance(&who);
+
+            // Create registry, map resource id, and mint nft
+            let registry_id = setup_nft(owner, token_id, resource_id);
+
+            // Whitelist destination chain
+            assert_ok!(ChainBridge::whitelist_chain(Origin::root(), dest_chain.clone()));
+
+            // Sponsor covers the anchoring state rent of the owner
+            <pallet_balances::Module<Test>>::make_free_balance_be(&sponsor, ENDOWED_BALANCE);
+            assert_ok!(Fees::grant_allowance(Origin::signed(sponsor), owner, None, None));
+            let owner_balance = balance_of(owner);
+
+            // The bridge fee is charged to the owner, not to the sponsor
+            assert_ok!(
+                PalletBridge::transfer_asset(
+                    Origin::signed(owner),
+                    vec![1],
+                    registry_id,
+                    token_id.clone(),
+                    dest_chain));
+            assert_eq!(balance_of(owner), owner_balance - NFT_TOKEN_FEE);
+            assert_eq!(balance_of(sponsor), ENDOWED_BALANCE);
+        })
+    }
+
 
 	#[test]
 	fn execute_remark() {
@@ -1,13 +1,32 @@
 /// Handling state rent fee payments for specific transactions
 use codec::{Decode, Encode};
 use frame_support::{
-    decl_event, decl_module, decl_storage,
+    decl_error, decl_event, decl_module, decl_storage,
     dispatch::DispatchResult,
     ensure,
-    traits::{Currency, EnsureOrigin, ExistenceRequirement, WithdrawReason},
-    weights::DispatchClass,
+    traits::{Currency, EnsureOrigin, ExistenceRequirement, Get, Imbalance, WithdrawReason},
+    weights::{DispatchClass, Weight},
 };
-use frame_system::ensure_root;
+use frame_system::{ensure_root, ensure_signed};
+use sp_runtime::{
+    traits::{AccountIdConversion, Saturating, Zero},
+    DispatchError, ModuleId, Perbill,
+};
+use sp_std::vec::Vec;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// Id of the fees module, used to derive the account of the treasury.
+const MODULE_ID: ModuleId = ModuleId(*b"fe/trsry");
+
+type NegativeImbalanceOf<T> = <pallet_balances::Module<T> as Currency<
+    <T as frame_system::Trait>::AccountId,
+>>::NegativeImbalance;
+
+/// Maximum number of fee changes scheduled for the same block, which bounds the weight of
+/// applying them in `on_initialize`.
+pub const MAX_FEE_CHANGES_PER_BLOCK: usize = 16;
 
 /// The module's configuration trait.
 pub trait Trait: frame_system::Trait + pallet_balances::Trait + pallet_authorship::Trait {
@@ -15,6 +34,201 @@ pub trait Trait: frame_system::Trait + pallet_balances::Trait + pallet_authorshi
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
     /// Required origin for changing fees
     type FeeChangeOrigin: EnsureOrigin<Self::Origin>;
+    /// Account of the eviction bounty pool, which receives the `eviction_bounty_pool` share of
+    /// the fees.
+    type EvictionBountyPoolAccount: Get<Self::AccountId>;
+    /// Distribution of the fees whose key has no distribution set through
+    /// `set_fee_distribution`.
+    type DefaultFeeDistribution: Get<FeeDistribution>;
+    /// Fee keys of the runtime, registered with their metadata at genesis and on every runtime
+    /// upgrade.
+    type FeeKeyRegistrations: Get<Vec<FeeKeyRegistration<Self::Hash, Self::Balance>>>;
+    /// Minimum number of blocks between scheduling a fee change and the change taking effect.
+    type MinFeeChangeNotice: Get<Self::BlockNumber>;
+    /// Fee keys of the fees that allowances cover, see `grant_allowance`.
+    type SponsoredFeeKeys: Get<Vec<Self::Hash>>;
+}
+
+/// What the price of a fee is charged for.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub enum FeeUnit {
+    /// The price is charged once per call.
+    PerCall,
+    /// The price is charged for each day something is stored.
+    PerDay,
+    /// The price is charged for each byte.
+    PerByte,
+}
+
+/// Human-readable description of a fee key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FeeKeyInfo {
+    /// Name of the fee, e.g. `anchor_state_rent`.
+    pub name: Vec<u8>,
+    pub unit: FeeUnit,
+    /// Name of the pallet charging the fee.
+    pub pallet: Vec<u8>,
+}
+
+/// A fee key to register with its metadata, see `Trait::FeeKeyRegistrations`. The initial price
+/// and distribution are only set if the key has none yet, such that the values set through
+/// `set_fee` and `set_fee_distribution` are kept across runtime upgrades.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FeeKeyRegistration<Hash, Balance> {
+    pub key: Hash,
+    pub info: FeeKeyInfo,
+    pub initial_price: Option<Balance>,
+    pub initial_distribution: Option<FeeDistribution>,
+}
+
+/// A registered fee key with its metadata, current price and distribution, as listed by
+/// `Module::fees`.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct FeeInfo<Hash, Balance> {
+    pub key: Hash,
+    /// Name of the fee, utf-8 encoded.
+    #[cfg_attr(feature = "std", serde(with = "utf8_string"))]
+    pub name: Vec<u8>,
+    pub unit: FeeUnit,
+    /// Name of the pallet charging the fee, utf-8 encoded.
+    #[cfg_attr(feature = "std", serde(with = "utf8_string"))]
+    pub pallet: Vec<u8>,
+    /// Current price of the fee, `None` if no price is set.
+    pub price: Option<Balance>,
+    pub distribution: FeeDistribution,
+}
+
+/// A price change of a fee scheduled with `schedule_fee_change`.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct ScheduledFeeChange<Hash, Balance, BlockNumber> {
+    pub key: Hash,
+    pub new_price: Balance,
+    /// Block at the start of which the new price applies.
+    pub effective_block: BlockNumber,
+}
+
+/// Allowance granted by a sponsor to cover the fees of an account, see `grant_allowance`.
+#[derive(Encode, Decode, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct Sponsorship<AccountId, Balance, BlockNumber> {
+    pub sponsor: AccountId,
+    /// Amount of fees the sponsor still covers, unlimited if `None`.
+    pub remaining: Option<Balance>,
+    /// Block from which the sponsor no longer covers fees, never if `None`.
+    pub expiry: Option<BlockNumber>,
+}
+
+impl<AccountId, Balance: PartialOrd + Zero, BlockNumber: PartialOrd>
+    Sponsorship<AccountId, Balance, BlockNumber>
+{
+    /// Returns true if the sponsorship has expired at `now`.
+    pub fn is_expired(&self, now: &BlockNumber) -> bool {
+        self.expiry.as_ref().map_or(false, |expiry| expiry <= now)
+    }
+
+    /// Returns true if the sponsorship has not expired at `now` and has some allowance left.
+    pub fn is_active(&self, now: &BlockNumber) -> bool {
+        !self.is_expired(now) && self.remaining.as_ref().map_or(true, |r| !r.is_zero())
+    }
+
+    /// Returns true if the sponsorship covers a fee of `amount` at `now`.
+    pub fn covers(&self, amount: &Balance, now: &BlockNumber) -> bool {
+        !self.is_expired(now) && self.remaining.as_ref().map_or(true, |r| r >= amount)
+    }
+}
+
+/// Estimated cost of submitting a call as a signed transaction, with the fees the call charges
+/// itself.
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct FeeEstimate<Balance> {
+    /// Base fee and weight fee of the transaction, adjusted by the current fee multiplier.
+    pub weight_fee: Balance,
+    /// Fee for the length of the transaction.
+    pub length_fee: Balance,
+    /// State rent of the anchors stored by the call.
+    pub state_rent: Balance,
+    /// Fee charged by the call on top of the state rent, e.g. the NFT mint fee.
+    pub call_fee: Balance,
+    /// Sum of all the fees.
+    pub total: Balance,
+}
+
+/// Serializes the names of fees as strings rather than byte arrays.
+#[cfg(feature = "std")]
+mod utf8_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&String::from_utf8_lossy(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        String::deserialize(deserializer).map(String::into_bytes)
+    }
+}
+
+/// Shares of a fee going to the block author, the treasury, the eviction bounty pool and
+/// burnt. The shares add up to the whole fee.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+pub struct FeeDistribution {
+    pub author: Perbill,
+    pub treasury: Perbill,
+    pub eviction_bounty_pool: Perbill,
+    pub burn: Perbill,
+}
+
+impl Default for FeeDistribution {
+    /// The whole fee goes to the block author.
+    fn default() -> Self {
+        FeeDistribution {
+            author: Perbill::one(),
+            treasury: Perbill::zero(),
+            eviction_bounty_pool: Perbill::zero(),
+            burn: Perbill::zero(),
+        }
+    }
+}
+
+impl FeeDistribution {
+    /// The whole fee is burnt.
+    pub fn burn_all() -> Self {
+        FeeDistribution {
+            author: Perbill::zero(),
+            treasury: Perbill::zero(),
+            eviction_bounty_pool: Perbill::zero(),
+            burn: Perbill::one(),
+        }
+    }
+
+    /// Returns true if the shares add up to the whole fee.
+    pub fn is_valid(&self) -> bool {
+        [
+            self.author,
+            self.treasury,
+            self.eviction_bounty_pool,
+            self.burn,
+        ]
+        .iter()
+        .fold(0u64, |total, share| total + share.deconstruct() as u64)
+            == Perbill::one().deconstruct() as u64
+    }
+}
+
+/// Amounts a fee payment was split into, see [FeeDistribution].
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FeeBreakdown<Balance> {
+    pub author: Balance,
+    pub treasury: Balance,
+    pub eviction_bounty_pool: Balance,
+    pub burn: Balance,
 }
 
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
@@ -28,28 +242,97 @@ decl_storage! {
     trait Store for Module<T: Trait> as Fees {
         Fees get(fn fee) : map hasher(opaque_blake2_256) T::Hash => Fee<T::Hash, T::Balance>;
 
+        /// Distribution of the fee of each key, `DefaultFeeDistribution` if not set.
+        FeeDistributions: map hasher(opaque_blake2_256) T::Hash => Option<FeeDistribution>;
+
+        /// Metadata of the registered fee keys, see `Trait::FeeKeyRegistrations`.
+        FeeKeys get(fn fee_key_info): map hasher(blake2_128_concat) T::Hash => Option<FeeKeyInfo>;
+
+        /// Fee changes to apply at the start of each block, in the order they were scheduled.
+        FeeChangesAt: map hasher(twox_64_concat) T::BlockNumber => Vec<(T::Hash, T::Balance)>;
+
+        /// Allowance covering the fees of each sponsored account.
+        Sponsorships get(fn sponsorship): map hasher(blake2_128_concat) T::AccountId
+            => Option<Sponsorship<T::AccountId, T::Balance, T::BlockNumber>>;
+
         Version: u64;
     }
     add_extra_genesis {
         // Anchoring state rent fee per day
         config(initial_fees): Vec<(T::Hash, T::Balance)>;
-        build(|config| Module::<T>::initialize_fees(&config.initial_fees))
+        build(|config| {
+            Module::<T>::initialize_fees(&config.initial_fees);
+            Module::<T>::register_fee_keys();
+        })
     }
 }
 
 decl_event!(
-    pub enum Event<T> where <T as frame_system::Trait>::Hash, <T as pallet_balances::Trait>::Balance {
+    pub enum Event<T> where
+        <T as frame_system::Trait>::AccountId,
+        <T as frame_system::Trait>::Hash,
+        <T as frame_system::Trait>::BlockNumber,
+        <T as pallet_balances::Trait>::Balance,
+        Breakdown = FeeBreakdown<<T as pallet_balances::Trait>::Balance>,
+    {
         FeeChanged(Hash, Balance),
+        /// A fee change was scheduled. [key, new price, effective block]
+        FeeChangeScheduled(Hash, Balance, BlockNumber),
+        /// The distribution of the fee of the key was changed. [key, distribution]
+        FeeDistributionChanged(Hash, FeeDistribution),
+        /// A fee was paid and distributed. [payer, key, breakdown]
+        FeePaid(AccountId, Hash, Breakdown),
+        /// A sponsor granted an allowance covering the fees of an account.
+        /// [sponsor, sponsored, amount, expiry]
+        AllowanceGranted(AccountId, AccountId, Option<Balance>, Option<BlockNumber>),
+        /// A sponsor paid a fee of an account out of its allowance.
+        /// [sponsor, sponsored, amount paid, remaining allowance]
+        AllowanceUsed(AccountId, AccountId, Balance, Option<Balance>),
+        /// The allowance of a sponsor was revoked by the sponsor or renounced by the sponsored
+        /// account. [sponsor, sponsored]
+        AllowanceRevoked(AccountId, AccountId),
+        /// Governance spent funds of the treasury. [beneficiary, amount]
+        TreasurySpent(AccountId, Balance),
     }
 );
 
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// The shares of a fee distribution do not add up to the whole fee.
+        InvalidFeeDistribution,
+        /// The effective block of a fee change is before the end of the notice period.
+        FeeChangeTooEarly,
+        /// `MAX_FEE_CHANGES_PER_BLOCK` fee changes are already scheduled for the block.
+        TooManyFeeChanges,
+        /// The account already has an allowance from another sponsor that can pay its fees.
+        AlreadySponsored,
+        /// The expiry of an allowance is not after the current block.
+        AllowanceExpiryInPast,
+        /// The account has no allowance from the sponsor.
+        NoAllowance,
+    }
+}
+
 decl_module! {
     /// The module declaration.
     pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+        type Error = Error<T>;
+
         // Initializing events
         // this is needed only if you are using events in your module
         fn deposit_event() = default;
 
+        /// Minimum number of blocks between scheduling a fee change and the change taking effect.
+        const MinFeeChangeNotice: T::BlockNumber = T::MinFeeChangeNotice::get();
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            Self::apply_fee_changes(now)
+        }
+
+        fn on_runtime_upgrade() -> Weight {
+            Self::register_fee_keys()
+        }
+
         /// Set the given fee for the key
         ///
         /// # <weight>
@@ -64,6 +347,141 @@ decl_module! {
             Self::deposit_event(RawEvent::FeeChanged(key, new_price));
             Ok(())
         }
+
+        /// Schedule the fee of the key to change to the given price at the start of
+        /// `effective_block`, which must be at least `MinFeeChangeNotice` blocks from now.
+        ///
+        /// # <weight>
+        /// - Independent of the arguments.
+        /// - Contains a limited number of reads and writes.
+        /// # </weight>
+        #[weight = (195_000_000, DispatchClass::Operational)]
+        pub fn schedule_fee_change(origin, key: T::Hash, new_price: T::Balance, effective_block: T::BlockNumber) -> DispatchResult {
+            Self::can_change_fee(origin)?;
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(
+                effective_block > now && effective_block - now >= T::MinFeeChangeNotice::get(),
+                Error::<T>::FeeChangeTooEarly
+            );
+            <FeeChangesAt<T>>::try_mutate(effective_block, |changes| {
+                ensure!(changes.len() < MAX_FEE_CHANGES_PER_BLOCK, Error::<T>::TooManyFeeChanges);
+                changes.push((key, new_price));
+                Ok::<_, Error<T>>(())
+            })?;
+
+            Self::deposit_event(RawEvent::FeeChangeScheduled(key, new_price, effective_block));
+            Ok(())
+        }
+
+        /// Grant an allowance covering the fees of `sponsored` with the balance of the caller,
+        /// up to `amount` in total and until the `expiry` block, without limit if not given.
+        /// The fees of `SponsoredFeeKeys` are charged to the sponsor while the allowance covers
+        /// them, else to `sponsored`. Other fees and transaction fees are not covered. Replaces
+        /// the previous allowance of the caller, and the allowance of another sponsor unless it
+        /// can pay the current fees of `SponsoredFeeKeys`, see `can_pay`.
+        ///
+        /// # <weight>
+        /// - Independent of the arguments.
+        /// - Contains a limited number of reads and writes.
+        /// # </weight>
+        #[weight = 195_000_000]
+        pub fn grant_allowance(
+            origin,
+            sponsored: T::AccountId,
+            amount: Option<T::Balance>,
+            expiry: Option<T::BlockNumber>
+        ) -> DispatchResult {
+            let sponsor = ensure_signed(origin)?;
+            let now = <frame_system::Module<T>>::block_number();
+            ensure!(expiry.map_or(true, |expiry| expiry > now), Error::<T>::AllowanceExpiryInPast);
+            if let Some(sponsorship) = <Sponsorships<T>>::get(&sponsored) {
+                ensure!(
+                    sponsorship.sponsor == sponsor || !Self::can_pay(&sponsorship, &now),
+                    Error::<T>::AlreadySponsored
+                );
+            }
+
+            <Sponsorships<T>>::insert(&sponsored, Sponsorship {
+                sponsor: sponsor.clone(),
+                remaining: amount,
+                expiry,
+            });
+            Self::deposit_event(RawEvent::AllowanceGranted(sponsor, sponsored, amount, expiry));
+            Ok(())
+        }
+
+        /// Revoke the allowance of the caller covering the fees of `sponsored`.
+        ///
+        /// # <weight>
+        /// - Independent of the arguments.
+        /// - Contains a limited number of reads and writes.
+        /// # </weight>
+        #[weight = 195_000_000]
+        pub fn revoke_allowance(origin, sponsored: T::AccountId) -> DispatchResult {
+            let sponsor = ensure_signed(origin)?;
+            ensure!(
+                Self::sponsorship(&sponsored).map_or(false, |s| s.sponsor == sponsor),
+                Error::<T>::NoAllowance
+            );
+
+            <Sponsorships<T>>::remove(&sponsored);
+            Self::deposit_event(RawEvent::AllowanceRevoked(sponsor, sponsored));
+            Ok(())
+        }
+
+        /// Renounce the allowance covering the fees of the caller, such that another sponsor
+        /// can grant one.
+        ///
+        /// # <weight>
+        /// - Independent of the arguments.
+        /// - Contains a limited number of reads and writes.
+        /// # </weight>
+        #[weight = 195_000_000]
+        pub fn renounce_allowance(origin) -> DispatchResult {
+            let sponsored = ensure_signed(origin)?;
+            let sponsorship = <Sponsorships<T>>::take(&sponsored).ok_or(Error::<T>::NoAllowance)?;
+
+            Self::deposit_event(RawEvent::AllowanceRevoked(sponsorship.sponsor, sponsored));
+            Ok(())
+        }
+
+        /// Set how the fee of the given key is split between the block author, the treasury,
+        /// the eviction bounty pool and burning.
+        ///
+        /// # <weight>
+        /// - Independent of the arguments.
+        /// - Contains a limited number of reads and writes.
+        /// # </weight>
+        #[weight = (195_000_000, DispatchClass::Operational)]
+        pub fn set_fee_distribution(origin, key: T::Hash, distribution: FeeDistribution) -> DispatchResult {
+            Self::can_change_fee(origin)?;
+            ensure!(distribution.is_valid(), Error::<T>::InvalidFeeDistribution);
+            <FeeDistributions<T>>::insert(key, distribution);
+
+            Self::deposit_event(RawEvent::FeeDistributionChanged(key, distribution));
+            Ok(())
+        }
+
+        /// Spend `amount` of the treasury, which is funded by the `treasury` share of the fees,
+        /// by transferring it to `beneficiary`.
+        ///
+        /// # <weight>
+        /// - Independent of the arguments.
+        /// - A single transfer.
+        /// # </weight>
+        #[weight = (195_000_000, DispatchClass::Operational)]
+        pub fn spend_treasury(origin, beneficiary: T::AccountId, amount: T::Balance) -> DispatchResult {
+            Self::can_change_fee(origin)?;
+            <pallet_balances::Module<T> as Currency<_>>::transfer(
+                &Self::treasury_account(),
+                &beneficiary,
+                amount,
+                ExistenceRequirement::AllowDeath,
+            )?;
+
+            Self::deposit_event(RawEvent::TreasurySpent(beneficiary, amount));
+            Ok(())
+        }
     }
 }
 
@@ -74,35 +492,119 @@ impl<T: Trait> Module<T> {
         ensure!(<Fees<T>>::contains_key(key), "fee not found for key");
 
         let single_fee = <Fees<T>>::get(key);
-        Self::pay_fee_to_author(from, single_fee.price)?;
-
-        Ok(())
+        Self::distribute_fee(from, key, single_fee.price)
     }
 
-    /// Burns Fee from account
-    pub fn burn_fee(from: &T::AccountId, fee: T::Balance) -> DispatchResult {
-        let _ = <pallet_balances::Module<T> as Currency<_>>::withdraw(
-            from,
-            fee,
-            WithdrawReason::Fee.into(),
-            ExistenceRequirement::KeepAlive,
-        )?;
+    /// Charges `amount` to the given account, or to its sponsor if its allowance covers it, for
+    /// the fee of the given key, and splits it as set by the distribution of the key.
+    pub fn distribute_fee(from: T::AccountId, key: T::Hash, amount: T::Balance) -> DispatchResult {
+        let distribution = Self::distribution_of(key);
+        let (payer, value) = Self::withdraw_fee(from, key, amount)?;
+
+        // the burnt share is what is left once the others are paid, rounding included
+        let (author, value) = value.split(distribution.author * amount);
+        let (treasury, value) = value.split(distribution.treasury * amount);
+        let (eviction_bounty_pool, burn) = value.split(distribution.eviction_bounty_pool * amount);
+        let breakdown = FeeBreakdown {
+            author: author.peek(),
+            treasury: treasury.peek(),
+            eviction_bounty_pool: eviction_bounty_pool.peek(),
+            burn: burn.peek(),
+        };
+
+        let author_account = <pallet_authorship::Module<T>>::author();
+        <pallet_balances::Module<T> as Currency<_>>::resolve_creating(&author_account, author);
+        <pallet_balances::Module<T> as Currency<_>>::resolve_creating(
+            &Self::treasury_account(),
+            treasury,
+        );
+        <pallet_balances::Module<T> as Currency<_>>::resolve_creating(
+            &T::EvictionBountyPoolAccount::get(),
+            eviction_bounty_pool,
+        );
+        // dropping the rest reduces the total issuance
+        drop(burn);
 
+        Self::deposit_event(RawEvent::FeePaid(payer, key, breakdown));
         Ok(())
     }
 
-    /// Pay the given fee
-    pub fn pay_fee_to_author(from: T::AccountId, fee: T::Balance) -> DispatchResult {
+    /// Returns true if the allowance is active at `now`, and both the allowance and the balance
+    /// of its sponsor cover the current fee of each of the `SponsoredFeeKeys`.
+    fn can_pay(
+        sponsorship: &Sponsorship<T::AccountId, T::Balance, T::BlockNumber>,
+        now: &T::BlockNumber,
+    ) -> bool {
+        let balance =
+            <pallet_balances::Module<T> as Currency<_>>::free_balance(&sponsorship.sponsor);
+        // the sponsor has to stay alive when paying a fee
+        let minimum_balance = <pallet_balances::Module<T> as Currency<_>>::minimum_balance();
+        sponsorship.is_active(now)
+            && T::SponsoredFeeKeys::get()
+                .into_iter()
+                .filter_map(Self::price_of)
+                .all(|price| {
+                    sponsorship.covers(&price, now)
+                        && balance >= price.saturating_add(minimum_balance)
+                })
+    }
+
+    /// Withdraws `amount` from the sponsor of the given account if its allowance covers it, the
+    /// fee key is one of `SponsoredFeeKeys` and the sponsor can pay, else from the account.
+    /// Returns the account charged and the withdrawn amount.
+    fn withdraw_fee(
+        from: T::AccountId,
+        key: T::Hash,
+        amount: T::Balance,
+    ) -> Result<(T::AccountId, NegativeImbalanceOf<T>), DispatchError> {
+        let now = <frame_system::Module<T>>::block_number();
+        let sponsorship = if T::SponsoredFeeKeys::get().contains(&key) {
+            <Sponsorships<T>>::get(&from)
+        } else {
+            None
+        };
+        if let Some(mut sponsorship) = sponsorship {
+            if sponsorship.covers(&amount, &now) {
+                let value = <pallet_balances::Module<T> as Currency<_>>::withdraw(
+                    &sponsorship.sponsor,
+                    amount,
+                    WithdrawReason::Fee.into(),
+                    ExistenceRequirement::KeepAlive,
+                );
+                if let Ok(value) = value {
+                    sponsorship.remaining = sponsorship
+                        .remaining
+                        .map(|remaining| remaining.saturating_sub(amount));
+                    <Sponsorships<T>>::insert(&from, &sponsorship);
+                    Self::deposit_event(RawEvent::AllowanceUsed(
+                        sponsorship.sponsor.clone(),
+                        from,
+                        amount,
+                        sponsorship.remaining,
+                    ));
+                    return Ok((sponsorship.sponsor, value));
+                }
+            }
+        }
+
         let value = <pallet_balances::Module<T> as Currency<_>>::withdraw(
             &from,
-            fee,
+            amount,
             WithdrawReason::Fee.into(),
             ExistenceRequirement::KeepAlive,
         )?;
+        Ok((from, value))
+    }
 
-        let author = <pallet_authorship::Module<T>>::author();
-        <pallet_balances::Module<T> as Currency<_>>::resolve_creating(&author, value);
-        Ok(())
+    /// Returns the distribution of the fee of the given key.
+    pub fn distribution_of(key: T::Hash) -> FeeDistribution {
+        <FeeDistributions<T>>::get(key).unwrap_or_else(T::DefaultFeeDistribution::get)
+    }
+
+    /// Account of the treasury, funded by the `treasury` share of the fees and spent through
+    /// `spend_treasury`.
+    pub fn treasury_account() -> T::AccountId {
+        MODULE_ID.into_account()
     }
 
     /// Returns the current fee for the key
@@ -116,6 +618,72 @@ impl<T: Trait> Module<T> {
         }
     }
 
+    /// Returns all the registered fee keys with their metadata, price and distribution.
+    pub fn fees() -> Vec<FeeInfo<T::Hash, T::Balance>> {
+        <FeeKeys<T>>::iter()
+            .map(|(key, info)| FeeInfo {
+                key,
+                name: info.name,
+                unit: info.unit,
+                pallet: info.pallet,
+                price: Self::price_of(key),
+                distribution: Self::distribution_of(key),
+            })
+            .collect()
+    }
+
+    /// Returns the fee changes scheduled after the current block, by effective block.
+    pub fn scheduled_fee_changes() -> Vec<ScheduledFeeChange<T::Hash, T::Balance, T::BlockNumber>> {
+        let mut changes = <FeeChangesAt<T>>::iter()
+            .flat_map(|(effective_block, changes)| {
+                changes
+                    .into_iter()
+                    .map(move |(key, new_price)| ScheduledFeeChange {
+                        key,
+                        new_price,
+                        effective_block,
+                    })
+            })
+            .collect::<Vec<_>>();
+        // the sort is stable, changes of the same block stay in the order they are applied
+        changes.sort_by_key(|change| change.effective_block);
+        changes
+    }
+
+    /// Applies the fee changes scheduled for the given block. Returns the weight consumed.
+    fn apply_fee_changes(now: T::BlockNumber) -> Weight {
+        let changes = <FeeChangesAt<T>>::take(now);
+        let count = changes.len() as Weight;
+        for (key, new_price) in changes {
+            Self::change_fee(key, new_price);
+            Self::deposit_event(RawEvent::FeeChanged(key, new_price));
+        }
+
+        T::DbWeight::get().reads_writes(1, 1 + count)
+    }
+
+    /// Registers the metadata of the fee keys of `Trait::FeeKeyRegistrations`, setting the
+    /// initial price and distribution of the keys which have none. Returns the weight consumed.
+    fn register_fee_keys() -> Weight {
+        let registrations = T::FeeKeyRegistrations::get();
+        let count = registrations.len() as Weight;
+        for registration in registrations {
+            <FeeKeys<T>>::insert(registration.key, registration.info);
+            if let Some(price) = registration.initial_price {
+                if !<Fees<T>>::contains_key(registration.key) {
+                    Self::change_fee(registration.key, price);
+                }
+            }
+            if let Some(distribution) = registration.initial_distribution {
+                if !<FeeDistributions<T>>::contains_key(registration.key) {
+                    <FeeDistributions<T>>::insert(registration.key, distribution);
+                }
+            }
+        }
+
+        T::DbWeight::get().reads_writes(2 * count, 3 * count)
+    }
+
     /// Returns true if the given origin can change the fee
     fn can_change_fee(origin: T::Origin) -> DispatchResult {
         T::FeeChangeOrigin::try_origin(origin)
@@ -148,8 +716,11 @@ mod tests {
     use super::*;
 
     use frame_support::{
-        assert_err, assert_noop, assert_ok, dispatch::DispatchError, impl_outer_origin,
-        ord_parameter_types, parameter_types, traits::FindAuthor, weights::Weight,
+        assert_err, assert_noop, assert_ok,
+        dispatch::DispatchError,
+        impl_outer_origin, ord_parameter_types, parameter_types,
+        traits::{FindAuthor, OnInitialize, OnRuntimeUpgrade},
+        weights::Weight,
         ConsensusEngineId,
     };
     use frame_system::EnsureSignedBy;
@@ -205,9 +776,35 @@ mod tests {
     ord_parameter_types! {
         pub const One: u64 = 1;
     }
+    parameter_types! {
+        pub const EvictionBountyPoolAccount: u64 = 200;
+        pub const MinFeeChangeNotice: u64 = 10;
+        pub FeeKeyRegistrations: Vec<FeeKeyRegistration<H256, u64>> = vec![
+            FeeKeyRegistration {
+                key: registered_fee_key(),
+                info: FeeKeyInfo {
+                    name: b"test_fee".to_vec(),
+                    unit: FeeUnit::PerCall,
+                    pallet: b"fees".to_vec(),
+                },
+                initial_price: Some(50),
+                initial_distribution: Some(FeeDistribution::burn_all()),
+            },
+        ];
+        pub SponsoredFeeKeys: Vec<H256> = vec![registered_fee_key()];
+    }
     impl Trait for Test {
         type Event = ();
         type FeeChangeOrigin = EnsureSignedBy<One, u64>;
+        type EvictionBountyPoolAccount = EvictionBountyPoolAccount;
+        type DefaultFeeDistribution = ();
+        type FeeKeyRegistrations = FeeKeyRegistrations;
+        type MinFeeChangeNotice = MinFeeChangeNotice;
+        type SponsoredFeeKeys = SponsoredFeeKeys;
+    }
+
+    fn registered_fee_key() -> H256 {
+        <Test as frame_system::Trait>::Hashing::hash_of(&(b"fees", b"test_fee"))
     }
     parameter_types! {
         pub const ExistentialDeposit: u64 = 1;
@@ -408,25 +1005,343 @@ mod tests {
     }
 
     #[test]
-    fn fee_burn_fee_from_account() {
+    fn fee_distribution_is_setable() {
+        new_test_ext().execute_with(|| {
+            let fee_key = <Test as frame_system::Trait>::Hashing::hash_of(&111111);
+            let distribution = FeeDistribution {
+                author: Perbill::from_percent(40),
+                treasury: Perbill::from_percent(30),
+                eviction_bounty_pool: Perbill::from_percent(20),
+                burn: Perbill::from_percent(10),
+            };
+
+            // the whole fee goes to the author by default
+            assert_eq!(Fees::distribution_of(fee_key), FeeDistribution::default());
+
+            assert_noop!(
+                Fees::set_fee_distribution(Origin::signed(2), fee_key, distribution),
+                BadOrigin
+            );
+            assert_noop!(
+                Fees::set_fee_distribution(
+                    Origin::signed(1),
+                    fee_key,
+                    FeeDistribution {
+                        burn: Perbill::from_percent(20),
+                        ..distribution
+                    }
+                ),
+                Error::<Test>::InvalidFeeDistribution
+            );
+            assert_ok!(Fees::set_fee_distribution(
+                Origin::signed(1),
+                fee_key,
+                distribution
+            ));
+            assert_eq!(Fees::distribution_of(fee_key), distribution);
+        });
+    }
+
+    #[test]
+    fn fee_payment_is_distributed() {
         new_test_ext().execute_with(|| {
-            let account_current_balance = <pallet_balances::Module<Test>>::free_balance(100);
-            let fee_amount: u64 = 10;
+            let fee_key = <Test as frame_system::Trait>::Hashing::hash_of(&111111);
+            let fee_price: <Test as pallet_balances::Trait>::Balance = 1000;
+            let distribution = FeeDistribution {
+                author: Perbill::from_percent(40),
+                treasury: Perbill::from_percent(30),
+                eviction_bounty_pool: Perbill::from_percent(20),
+                burn: Perbill::from_percent(10),
+            };
+            assert_ok!(Fees::set_fee(Origin::signed(1), fee_key, fee_price));
+            assert_ok!(Fees::set_fee_distribution(
+                Origin::signed(1),
+                fee_key,
+                distribution
+            ));
+            let total_issuance = <pallet_balances::Module<Test>>::total_issuance();
 
-            //first time has enough funds to burn
-            assert_ok!(Fees::burn_fee(&100, fee_amount));
-            let account_new_balance = <pallet_balances::Module<Test>>::free_balance(100);
-            assert_eq!(account_current_balance - fee_amount, account_new_balance);
+            assert_ok!(Fees::pay_fee(1, fee_key));
+            assert_eq!(
+                <pallet_balances::Module<Test>>::free_balance(&1),
+                100000 - 1000
+            );
+            assert_eq!(
+                <pallet_balances::Module<Test>>::free_balance(&100),
+                100 + 400
+            );
+            assert_eq!(
+                <pallet_balances::Module<Test>>::free_balance(&Fees::treasury_account()),
+                300
+            );
+            assert_eq!(<pallet_balances::Module<Test>>::free_balance(&200), 200);
+            assert_eq!(
+                <pallet_balances::Module<Test>>::total_issuance(),
+                total_issuance - 100
+            );
+        });
+    }
 
-            //second time burn will lead to account having insufficient balance
-            assert_err!(
-                Fees::burn_fee(&100, account_new_balance + 1),
-                DispatchError::Module {
-                    index: 0,
-                    error: 3,
-                    message: Some("InsufficientBalance"),
+    #[test]
+    fn treasury_is_spent_by_governance() {
+        new_test_ext().execute_with(|| {
+            let fee_key = <Test as frame_system::Trait>::Hashing::hash_of(&111111);
+            assert_ok!(Fees::set_fee(Origin::signed(1), fee_key, 1000));
+            assert_ok!(Fees::set_fee_distribution(
+                Origin::signed(1),
+                fee_key,
+                FeeDistribution {
+                    author: Perbill::zero(),
+                    treasury: Perbill::one(),
+                    eviction_bounty_pool: Perbill::zero(),
+                    burn: Perbill::zero(),
                 }
+            ));
+            assert_ok!(Fees::pay_fee(1, fee_key));
+            let treasury = Fees::treasury_account();
+            assert_eq!(
+                <pallet_balances::Module<Test>>::free_balance(&treasury),
+                1000
+            );
+
+            assert_noop!(Fees::spend_treasury(Origin::signed(2), 3, 400), BadOrigin);
+            assert_noop!(
+                Fees::spend_treasury(Origin::signed(1), 3, 1001),
+                pallet_balances::Error::<Test, _>::InsufficientBalance
+            );
+
+            assert_ok!(Fees::spend_treasury(Origin::signed(1), 3, 400));
+            assert_eq!(
+                <pallet_balances::Module<Test>>::free_balance(&treasury),
+                600
+            );
+            assert_eq!(<pallet_balances::Module<Test>>::free_balance(&3), 400);
+        });
+    }
+
+    #[test]
+    fn fee_keys_are_registered() {
+        new_test_ext().execute_with(|| {
+            let fee_key = registered_fee_key();
+            let unregistered_key = <Test as frame_system::Trait>::Hashing::hash_of(&111111);
+            assert_eq!(Fees::fees(), vec![]);
+
+            <Fees as OnRuntimeUpgrade>::on_runtime_upgrade();
+            assert_eq!(
+                Fees::fee_key_info(fee_key).unwrap().name,
+                b"test_fee".to_vec()
+            );
+            assert_eq!(Fees::price_of(fee_key), Some(50));
+            assert_eq!(Fees::distribution_of(fee_key).burn, Perbill::one());
+
+            // keys set with `set_fee` only are not listed
+            assert_ok!(Fees::set_fee(Origin::signed(1), unregistered_key, 10));
+            assert_ok!(Fees::set_fee(Origin::signed(1), fee_key, 70));
+
+            // registering again keeps the price set
+            <Fees as OnRuntimeUpgrade>::on_runtime_upgrade();
+            assert_eq!(
+                Fees::fees(),
+                vec![FeeInfo {
+                    key: fee_key,
+                    name: b"test_fee".to_vec(),
+                    unit: FeeUnit::PerCall,
+                    pallet: b"fees".to_vec(),
+                    price: Some(70),
+                    distribution: Fees::distribution_of(fee_key),
+                }]
+            );
+        });
+    }
+
+    #[test]
+    fn fee_change_is_scheduled() {
+        new_test_ext().execute_with(|| {
+            let fee_key = <Test as frame_system::Trait>::Hashing::hash_of(&111111);
+            System::set_block_number(1);
+            assert_ok!(Fees::set_fee(Origin::signed(1), fee_key, 100));
+
+            assert_noop!(
+                Fees::schedule_fee_change(Origin::signed(2), fee_key, 200, 11),
+                BadOrigin
+            );
+            // the notice period is 10 blocks
+            assert_noop!(
+                Fees::schedule_fee_change(Origin::signed(1), fee_key, 200, 10),
+                Error::<Test>::FeeChangeTooEarly
+            );
+            assert_ok!(Fees::schedule_fee_change(
+                Origin::signed(1),
+                fee_key,
+                300,
+                20
+            ));
+            assert_ok!(Fees::schedule_fee_change(
+                Origin::signed(1),
+                fee_key,
+                200,
+                11
+            ));
+            assert_eq!(
+                Fees::scheduled_fee_changes(),
+                vec![
+                    ScheduledFeeChange {
+                        key: fee_key,
+                        new_price: 200,
+                        effective_block: 11,
+                    },
+                    ScheduledFeeChange {
+                        key: fee_key,
+                        new_price: 300,
+                        effective_block: 20,
+                    },
+                ]
+            );
+
+            Fees::on_initialize(10);
+            assert_eq!(Fees::price_of(fee_key), Some(100));
+            Fees::on_initialize(11);
+            assert_eq!(Fees::price_of(fee_key), Some(200));
+            assert_eq!(Fees::scheduled_fee_changes().len(), 1);
+            Fees::on_initialize(20);
+            assert_eq!(Fees::price_of(fee_key), Some(300));
+            assert_eq!(Fees::scheduled_fee_changes(), vec![]);
+        });
+    }
+
+    #[test]
+    fn fee_changes_per_block_are_bounded() {
+        new_test_ext().execute_with(|| {
+            for i in 0..MAX_FEE_CHANGES_PER_BLOCK {
+                let fee_key = <Test as frame_system::Trait>::Hashing::hash_of(&i);
+                assert_ok!(Fees::schedule_fee_change(
+                    Origin::signed(1),
+                    fee_key,
+                    100,
+                    20
+                ));
+            }
+
+            let fee_key = <Test as frame_system::Trait>::Hashing::hash_of(&111111);
+            assert_noop!(
+                Fees::schedule_fee_change(Origin::signed(1), fee_key, 100, 20),
+                Error::<Test>::TooManyFeeChanges
+            );
+            assert_ok!(Fees::schedule_fee_change(
+                Origin::signed(1),
+                fee_key,
+                100,
+                21
+            ));
+        });
+    }
+
+    #[test]
+    fn sponsor_pays_fees_covered_by_allowance() {
+        new_test_ext().execute_with(|| {
+            let fee_key = registered_fee_key();
+            let unsponsored_fee_key = <Test as frame_system::Trait>::Hashing::hash_of(&111111);
+            let insufficient_balance = DispatchError::Module {
+                index: 0,
+                error: 3,
+                message: Some("InsufficientBalance"),
+            };
+            System::set_block_number(1);
+            assert_ok!(Fees::set_fee(Origin::signed(1), fee_key, 1000));
+
+            // account 3 is not endowed and sponsored by account 1 for 1500 until block 10
+            assert_noop!(
+                Fees::grant_allowance(Origin::signed(1), 3, Some(1500), Some(1)),
+                Error::<Test>::AllowanceExpiryInPast
+            );
+            assert_ok!(Fees::grant_allowance(
+                Origin::signed(1),
+                3,
+                Some(1500),
+                Some(10)
+            ));
+            assert_noop!(
+                Fees::grant_allowance(Origin::signed(2), 3, None, None),
+                Error::<Test>::AlreadySponsored
+            );
+
+            assert_ok!(Fees::pay_fee(3, fee_key));
+            assert_eq!(
+                <pallet_balances::Module<Test>>::free_balance(&1),
+                100000 - 1000
+            );
+            assert_eq!(Fees::sponsorship(3).unwrap().remaining, Some(500));
+
+            // only the fees of the sponsored keys are covered
+            assert_ok!(Fees::set_fee(Origin::signed(1), unsponsored_fee_key, 100));
+            assert_err!(Fees::pay_fee(3, unsponsored_fee_key), insufficient_balance);
+            assert_eq!(Fees::sponsorship(3).unwrap().remaining, Some(500));
+
+            // the rest of the allowance does not cover the fee
+            assert_err!(Fees::pay_fee(3, fee_key), insufficient_balance);
+
+            // another sponsor can take over an allowance that cannot pay the fee
+            assert_ok!(Fees::grant_allowance(Origin::signed(2), 3, None, None));
+            assert_eq!(Fees::sponsorship(3).unwrap().sponsor, 2);
+
+            // the allowance no longer covers fees once expired
+            assert_ok!(Fees::revoke_allowance(Origin::signed(2), 3));
+            assert_ok!(Fees::grant_allowance(
+                Origin::signed(1),
+                3,
+                Some(1500),
+                Some(10)
+            ));
+            System::set_block_number(10);
+            assert_err!(Fees::pay_fee(3, fee_key), insufficient_balance);
+
+            // another sponsor can take over an expired allowance
+            assert_ok!(Fees::grant_allowance(Origin::signed(2), 3, None, None));
+            assert_ok!(Fees::pay_fee(3, fee_key));
+            assert_eq!(
+                <pallet_balances::Module<Test>>::free_balance(&2),
+                100000 - 1000
+            );
+            assert_eq!(Fees::sponsorship(3).unwrap().remaining, None);
+
+            assert_noop!(
+                Fees::revoke_allowance(Origin::signed(1), 3),
+                Error::<Test>::NoAllowance
+            );
+            assert_ok!(Fees::revoke_allowance(Origin::signed(2), 3));
+            assert_eq!(Fees::sponsorship(3), None);
+            assert_noop!(
+                Fees::renounce_allowance(Origin::signed(3)),
+                Error::<Test>::NoAllowance
+            );
+        });
+    }
+
+    #[test]
+    fn allowance_of_sponsor_that_cannot_pay_can_be_replaced() {
+        new_test_ext().execute_with(|| {
+            let fee_key = registered_fee_key();
+            System::set_block_number(1);
+            assert_ok!(Fees::set_fee(Origin::signed(1), fee_key, 1000));
+
+            // account 4 is not endowed and cannot pay the fee of account 3
+            assert_ok!(Fees::grant_allowance(Origin::signed(4), 3, None, None));
+            assert_ok!(Fees::grant_allowance(Origin::signed(1), 3, None, None));
+            assert_eq!(Fees::sponsorship(3).unwrap().sponsor, 1);
+
+            // an allowance that can pay the fee is kept until the sponsor lacks the balance
+            assert_noop!(
+                Fees::grant_allowance(Origin::signed(2), 3, None, None),
+                Error::<Test>::AlreadySponsored
             );
+            assert_ok!(<pallet_balances::Module<Test> as Currency<_>>::transfer(
+                &1,
+                &4,
+                100000 - 1000,
+                ExistenceRequirement::AllowDeath
+            ));
+            assert_ok!(Fees::grant_allowance(Origin::signed(2), 3, None, None));
+            assert_eq!(Fees::sponsorship(3).unwrap().sponsor, 2);
         });
     }
 }
@@ -1,10 +1,17 @@
 //! Some configurable implementations as associated type for the substrate runtime.
 
-use node_primitives::Balance;
-use sp_runtime::traits::Convert;
+use node_primitives::{AccountId, Balance, Index, Signature};
+use sp_runtime::generic::Era;
+use sp_runtime::traits::{Convert, StaticLookup};
 use frame_support::traits::{OnUnbalanced, Currency};
-use crate::{Balances, Authorship, NegativeImbalance};
-use frame_support::weights::{WeightToFeeCoefficient, WeightToFeePolynomial, WeightToFeeCoefficients};
+use sp_runtime::traits::{DispatchInfoOf, SignedExtension};
+use sp_runtime::transaction_validity::{InvalidTransaction, TransactionValidity, TransactionValidityError, ValidTransaction};
+use sp_runtime::RuntimeDebug;
+use crate::{Anchor, Balances, Authorship, Call, Indices, NegativeImbalance, Nfts, Registry, Runtime, TransactionPayment, UncheckedExtrinsic};
+use crate::{nfts, va_registry};
+use crate::fees::FeeEstimate;
+use codec::{Decode, Encode};
+use frame_support::weights::{GetDispatchInfo, WeightToFeeCoefficient, WeightToFeePolynomial, WeightToFeeCoefficients};
 use smallvec::smallvec;
 use sp_arithmetic::Perbill;
 
@@ -72,3 +79,87 @@ impl WeightToFeePolynomial for WeightToFee {
 		})
 	}
 }
+
+/// Estimates the cost of submitting `call` as a signed transaction without tip, on top of the
+/// state rent and the fee the call charges itself. The transaction is assumed to be signed by an
+/// account id with an sr25519 signature, with a mortal era and the largest nonce, such that the
+/// length fee is not underestimated.
+pub fn estimate_fee(call: Call, state_rent: Balance, call_fee: Balance) -> FeeEstimate<Balance> {
+	let info = call.get_dispatch_info();
+	let extra = (
+		frame_system::CheckSpecVersion::<Runtime>::new(),
+		frame_system::CheckTxVersion::<Runtime>::new(),
+		frame_system::CheckGenesis::<Runtime>::new(),
+		frame_system::CheckEra::<Runtime>::from(Era::mortal(256, 0)),
+		frame_system::CheckNonce::<Runtime>::from(Index::max_value()),
+		frame_system::CheckWeight::<Runtime>::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+		CheckProofsLimits,
+	);
+	let signature: Signature = sp_core::sr25519::Signature::from_raw([0; 64]).into();
+	let len = UncheckedExtrinsic::new_signed(call, Indices::unlookup(AccountId::default()), signature, extra)
+		.encode()
+		.len() as u32;
+
+	// the length fee is the only part of the transaction fee depending on the length
+	let weight_fee = TransactionPayment::compute_fee(0, &info, 0);
+	let length_fee = TransactionPayment::compute_fee(len, &info, 0).saturating_sub(weight_fee);
+	FeeEstimate {
+		weight_fee,
+		length_fee,
+		state_rent,
+		call_fee,
+		total: weight_fee
+			.saturating_add(length_fee)
+			.saturating_add(state_rent)
+			.saturating_add(call_fee),
+	}
+}
+
+/// Rejects NFT mints whose proofs exceed the limits of their module before they are dispatched,
+/// such that oversized proofs are neither kept in the transaction pool nor hashed in a block.
+/// Covers `nfts::validate_mint` and `va_registry::mint`, whose own checks only run on dispatch.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct CheckProofsLimits;
+
+impl CheckProofsLimits {
+	/// Returns whether the proofs of the call, if any, are within the limits of its module.
+	fn within_limits(call: &Call) -> bool {
+		match call {
+			Call::Nfts(nfts::Call::validate_mint(_, _, pfs, _, _, _)) =>
+				Nfts::check_proofs_limits(pfs).is_ok(),
+			Call::Registry(va_registry::Call::mint(_, _, _, _, mint_info)) =>
+				Registry::check_proofs_limits(&mint_info.proofs, mint_info.multi_proof.as_ref()).is_ok()
+					&& mint_info.evicted_anchor.as_ref().map_or(true, |evicted| {
+						Anchor::check_evicted_anchor_proof_limits(&evicted.proof).is_ok()
+					}),
+			_ => true,
+		}
+	}
+}
+
+impl SignedExtension for CheckProofsLimits {
+	const IDENTIFIER: &'static str = "CheckProofsLimits";
+	type AccountId = AccountId;
+	type Call = Call;
+	type AdditionalSigned = ();
+	type Pre = ();
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> {
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		_who: &Self::AccountId,
+		call: &Self::Call,
+		_info: &DispatchInfoOf<Self::Call>,
+		_len: usize,
+	) -> TransactionValidity {
+		if Self::within_limits(call) {
+			Ok(ValidTransaction::default())
+		} else {
+			InvalidTransaction::ExhaustsResources.into()
+		}
+	}
+}
@@ -22,7 +22,7 @@ pub use node_primitives::{AccountId, Signature};
 use node_primitives::{AccountIndex, Balance, BlockNumber, Hash, Index, Moment};
 use sp_api::{decl_runtime_apis, impl_runtime_apis};
 use sp_runtime::{
-	Perbill, Perquintill, ApplyExtrinsicResult,
+	Perbill, Perquintill, ApplyExtrinsicResult, DispatchError,
 	impl_opaque_keys, generic, create_runtime_str, FixedPointNumber,
 };
 use sp_runtime::curve::PiecewiseLinear;
@@ -45,7 +45,10 @@ pub use pallet_transaction_payment::{Multiplier, TargetedFeeAdjustment};
 use frame_system::{EnsureSigned, EnsureRoot};
 use pallet_session::{historical as pallet_session_historical};
 use sp_inherents::{InherentData, CheckInherentsResult};
-use crate::anchor::AnchorData;
+use crate::anchor::{AnchorCounts, AnchorData, AnchorWithEvictDate};
+use crate::proofs::{DocumentLayoutVersion, HashFunction, ProofsValidation};
+use crate::fees::{FeeEstimate, FeeInfo, ScheduledFeeChange, Sponsorship};
+use crate::va_registry::types::{RegistryId, TokenId};
 use pallet_collective::EnsureProportionMoreThan;
 use static_assertions::const_assert;
 
@@ -67,13 +70,13 @@ use bridge_mapping;
 pub mod anchor;
 
 /// Fees for TXs
-mod fees;
+pub mod fees;
 
 /// common utilities
 mod common;
 
 /// proofs utilities
-mod proofs;
+pub mod proofs;
 
 /// nft module
 mod nfts;
@@ -85,7 +88,7 @@ mod rad_claims;
 mod bridge;
 
 /// verifiable attributes registry module
-mod va_registry;
+pub mod va_registry;
 
 /// nft module
 mod nft;
@@ -117,10 +120,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     // and set impl_version to 0. If only runtime
     // implementation changes and behavior does not, then leave spec_version as
     // is and increment impl_version.
-    spec_version: 242,
+    spec_version: 266,
     impl_version: 0,
     apis: RUNTIME_API_VERSIONS,
-    transaction_version: 1,
+    transaction_version: 2,
 };
 
 /// Native version.
@@ -654,6 +657,7 @@ impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for R
             frame_system::CheckNonce::<Runtime>::from(nonce),
             frame_system::CheckWeight::<Runtime>::new(),
             pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
+            impls::CheckProofsLimits,
         );
         let raw_payload = SignedPayload::new(call, extra)
             .map_err(|e| {
@@ -762,17 +766,81 @@ impl pallet_identity::Trait for Runtime {
     type WeightInfo = ();
 }
 
-impl anchor::Trait for Runtime {}
+parameter_types! {
+    /// This is the maximum expected time for document consensus to take place between a pre-commit
+    /// of an anchor and a commit to be received for the pre-committed anchor. Currently we expect to
+    /// provide around 80 mins for this.
+    pub const PreCommitExpirationDurationBlocks: BlockNumber = 80 * MINUTES;
+    /// MUST be higher than 1 to assure that pre-commits are around during their validity time frame.
+    pub const PreCommitEvictionBucketMultiplier: u32 = 5;
+    pub const MaxLoopInTx: u64 = 500;
+    /// date 3000-01-01 -> 376200 days from unix epoch
+    pub const StorageMaxDays: u32 = 376200;
+    /// A proof of an evicted anchor is a path in the child trie of the anchors of a day, of at
+    /// most 16 trie nodes taking 16 KiB together.
+    pub const MaxEvictedAnchorProofNodes: u32 = 16;
+    pub const MaxEvictedAnchorProofSize: u32 = 16 * 1024;
+}
+
+impl anchor::Trait for Runtime {
+    type Event = Event;
+    type WeightInfo = ();
+    type PreCommitExpirationDurationBlocks = PreCommitExpirationDurationBlocks;
+    type PreCommitEvictionBucketMultiplier = PreCommitEvictionBucketMultiplier;
+    type MaxLoopInTx = MaxLoopInTx;
+    type StorageMaxDays = StorageMaxDays;
+    type MaxEvictedAnchorProofNodes = MaxEvictedAnchorProofNodes;
+    type MaxEvictedAnchorProofSize = MaxEvictedAnchorProofSize;
+    type AdminOrigin = pallet_collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+}
+
+parameter_types! {
+	pub EvictionBountyPoolAccount: AccountId = Anchor::eviction_bounty_pool_account();
+	/// Fees go to the block author, except for 10% that go to the eviction bounty pool, unless
+	/// another distribution is set for their key.
+	pub const DefaultFeeDistribution: fees::FeeDistribution = fees::FeeDistribution {
+		author: Perbill::from_percent(90),
+		treasury: Perbill::from_percent(0),
+		eviction_bounty_pool: Perbill::from_percent(10),
+		burn: Perbill::from_percent(0),
+	};
+	/// Fee keys of the runtime, listed with their metadata by the fees RPC.
+	pub FeeKeyRegistrations: Vec<fees::FeeKeyRegistration<Hash, Balance>> = [
+		Anchor::fee_key_registrations(),
+		Nfts::fee_key_registrations(),
+		PalletBridge::fee_key_registrations(),
+	].concat();
+	/// Fee changes are announced at least a day before they apply.
+	pub const MinFeeChangeNotice: BlockNumber = 1 * DAYS;
+	/// Sponsors only cover the anchoring state rent.
+	pub SponsoredFeeKeys: Vec<Hash> = vec![Anchor::fee_key()];
+}
 
 /// Fees module implementation
 impl fees::Trait for Runtime {
 	type Event = Event;
 	/// A straight majority of the council can change the fees.
 	type FeeChangeOrigin = pallet_collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+	type EvictionBountyPoolAccount = EvictionBountyPoolAccount;
+	type DefaultFeeDistribution = DefaultFeeDistribution;
+	type FeeKeyRegistrations = FeeKeyRegistrations;
+	type MinFeeChangeNotice = MinFeeChangeNotice;
+	type SponsoredFeeKeys = SponsoredFeeKeys;
+}
+
+parameter_types! {
+	/// Maximum number of proofs of an NFT mint.
+	pub const MaxProofs: u32 = 64;
+	/// Maximum number of hashes of each proof of an NFT mint.
+	pub const MaxProofHashes: u32 = 32;
+	/// Maximum length in bytes of the value and of the property of each proof of an NFT mint.
+	pub const MaxProofFieldLength: u32 = 1024;
 }
 
 impl nfts::Trait for Runtime {
     type Event = Event;
+    type MaxProofs = MaxProofs;
+    type MaxProofHashes = MaxProofHashes;
 }
 
 parameter_types! {
@@ -851,6 +919,10 @@ impl pallet_vesting::Trait for Runtime {
 
 impl va_registry::Trait for Runtime {
     type Event = Event;
+    type WeightInfo = ();
+    type MaxProofs = MaxProofs;
+    type MaxProofHashes = MaxProofHashes;
+    type MaxProofFieldLength = MaxProofFieldLength;
 }
 
 impl nft::Trait for Runtime {
@@ -891,7 +963,7 @@ construct_runtime!(
 		AuthorityDiscovery: pallet_authority_discovery::{Module, Call, Config},
 		Offences: pallet_offences::{Module, Call, Storage, Event},
         RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Module, Call, Storage},
-		Anchor: anchor::{Module, Call, Storage},
+		Anchor: anchor::{Module, Call, Storage, Event<T>},
 		Fees: fees::{Module, Call, Storage, Event<T>, Config<T>},
 		Nfts: nfts::{Module, Call, Event<T>},
 		MultiAccount: substrate_pallet_multi_account::{Module, Call, Storage, Event<T>, Config<T>},
@@ -905,7 +977,7 @@ construct_runtime!(
 		Multisig: pallet_multisig::{Module, Call, Storage, Event<T>},
         RadClaims: rad_claims::{Module, Call, Storage, Event<T>, ValidateUnsigned},
         Vesting: pallet_vesting::{Module, Call, Storage, Event<T>, Config<T>},
-		Registry: va_registry::{Module, Call, Storage, Event<T>},
+		Registry: va_registry::{Module, Call, Storage, Event<T>, Config},
 		Nft: nft::{Module, Call, Storage, Event<T>},
         BridgeMapping: bridge_mapping::{Module, Call, Storage},
 	}
@@ -930,6 +1002,7 @@ pub type SignedExtra = (
     frame_system::CheckNonce<Runtime>,
     frame_system::CheckWeight<Runtime>,
     pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+    impls::CheckProofsLimits,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -943,8 +1016,63 @@ pub type Executive = frame_executive::Executive<Runtime, Block, frame_system::Ch
 
 decl_runtime_apis! {
     /// The API to query anchoring info.
+    ///
+    /// Version 2 added all methods but `get_anchor_by_id`.
+    #[api_version(2)]
     pub trait AnchorApi {
         fn get_anchor_by_id(id: Hash) -> Option<AnchorData<Hash, BlockNumber>>;
+
+        /// Returns the block in which the anchors of the given eviction day were evicted.
+        fn get_evicted_anchor_block(day: u32) -> Option<BlockNumber>;
+
+        /// Returns up to `limit` anchors starting at anchor index `start`.
+        fn get_anchors_by_index_range(start: u64, limit: u32) -> Vec<AnchorWithEvictDate<Hash, BlockNumber>>;
+
+        /// Returns up to `limit` anchors to be evicted on `day`, following the anchor id `start_after`.
+        fn get_anchors_by_evict_date(day: u32, start_after: Option<Hash>, limit: u32) -> Vec<AnchorWithEvictDate<Hash, BlockNumber>>;
+
+        /// Returns the counters of the anchor index.
+        fn get_anchor_counts() -> AnchorCounts;
+
+        /// Returns the id, the committing account and the data of the anchors committed in the
+        /// queried block, given the `extrinsics` of that block. The committing account of an
+        /// anchor is the signer of the extrinsic that committed it.
+        fn get_committed_anchors(extrinsics: Vec<<Block as BlockT>::Extrinsic>) -> Vec<(Hash, AccountId, AnchorData<Hash, BlockNumber>)>;
+    }
+
+    /// The API to validate document proofs without a transaction.
+    pub trait ProofsApi {
+        /// Validates the proofs of the document anchored as `anchor_id` the same way as a mint
+        /// of the verifiable attributes registry with the given hash function does, with the
+        /// static proofs of the given document layout version.
+        fn validate_proofs(anchor_id: Hash, document_layout: DocumentLayoutVersion, hasher: HashFunction, static_proofs: Vec<Hash>, proofs: Vec<va_registry::types::Proof<Hash>>) -> ProofsValidation;
+    }
+
+    /// The API to query the fees.
+    pub trait FeesApi {
+        /// Returns the registered fee keys with their metadata, price and distribution.
+        fn fees() -> Vec<FeeInfo<Hash, Balance>>;
+
+        /// Returns the upcoming fee changes, by effective block.
+        fn scheduled_fee_changes() -> Vec<ScheduledFeeChange<Hash, Balance, BlockNumber>>;
+
+        /// Estimates the cost of committing an anchor stored until the given date with
+        /// `anchor::commit`.
+        fn estimate_commit_fee(stored_until_date: Moment) -> Result<FeeEstimate<Balance>, DispatchError>;
+
+        /// Estimates the cost of minting an NFT with `nfts::validate_mint` and the given arguments.
+        fn estimate_nft_mint_fee(anchor_id: Hash, deposit_address: [u8; 20], proofs: Vec<proofs::Proof>, document_layout: DocumentLayoutVersion, static_proofs: Vec<Hash>, dest_id: chainbridge::ChainId) -> Result<FeeEstimate<Balance>, DispatchError>;
+
+        /// Estimates the cost of transferring native tokens through the bridge with
+        /// `bridge::transfer_native` and the given arguments.
+        fn estimate_native_transfer_fee(amount: Balance, recipient: Vec<u8>, dest_id: chainbridge::ChainId) -> FeeEstimate<Balance>;
+
+        /// Estimates the cost of transferring an NFT through the bridge with
+        /// `bridge::transfer_asset` and the given arguments.
+        fn estimate_nft_transfer_fee(recipient: Vec<u8>, from_registry: RegistryId, token_id: TokenId, dest_id: chainbridge::ChainId) -> Result<FeeEstimate<Balance>, DispatchError>;
+
+        /// Returns the allowance covering the fees of the given account, if any.
+        fn sponsorship(account: AccountId) -> Option<Sponsorship<AccountId, Balance, BlockNumber>>;
     }
 }
 
@@ -1118,7 +1246,89 @@ impl_runtime_apis! {
 
 	impl self::AnchorApi<Block> for Runtime {
 		fn get_anchor_by_id(id: Hash) -> Option<AnchorData<Hash, BlockNumber>> {
-			Anchor::get_anchor_by_id(id)
+			Anchor::get_anchor_by_id(id).ok().flatten()
+		}
+
+		fn get_evicted_anchor_block(day: u32) -> Option<BlockNumber> {
+			Anchor::get_evicted_anchor_block_by_day(day)
+		}
+
+		fn get_anchors_by_index_range(start: u64, limit: u32) -> Vec<AnchorWithEvictDate<Hash, BlockNumber>> {
+			Anchor::get_anchors_by_index_range(start, limit)
+		}
+
+		fn get_anchors_by_evict_date(day: u32, start_after: Option<Hash>, limit: u32) -> Vec<AnchorWithEvictDate<Hash, BlockNumber>> {
+			Anchor::get_anchors_by_evict_date(day, start_after, limit)
+		}
+
+		fn get_anchor_counts() -> AnchorCounts {
+			Anchor::get_anchor_counts()
+		}
+
+		fn get_committed_anchors(extrinsics: Vec<<Block as BlockT>::Extrinsic>) -> Vec<(Hash, AccountId, AnchorData<Hash, BlockNumber>)> {
+			// events of a block stay in storage until the next block is initialized
+			System::events()
+				.into_iter()
+				.filter_map(|record| match (record.phase, record.event) {
+					(
+						frame_system::Phase::ApplyExtrinsic(index),
+						Event::anchor(anchor::RawEvent::AnchorCommitted(anchor_id, _, _)),
+					) => {
+						let (address, _, _) = extrinsics.get(index as usize)?.signature.as_ref()?;
+						let who = Indices::lookup(address.clone()).ok()?;
+						Anchor::get_anchor_by_id(anchor_id)
+							.ok()
+							.flatten()
+							.map(|anchor| (anchor_id, who, anchor))
+					}
+					_ => None,
+				})
+				.collect()
+		}
+	}
+
+	impl self::ProofsApi<Block> for Runtime {
+		fn validate_proofs(anchor_id: Hash, document_layout: DocumentLayoutVersion, hasher: HashFunction, static_proofs: Vec<Hash>, proofs: Vec<va_registry::types::Proof<Hash>>) -> ProofsValidation {
+			Registry::validate_document_proofs(anchor_id, document_layout, hasher, static_proofs, proofs)
+		}
+	}
+
+	impl self::FeesApi<Block> for Runtime {
+		fn fees() -> Vec<FeeInfo<Hash, Balance>> {
+			Fees::fees()
+		}
+
+		fn scheduled_fee_changes() -> Vec<ScheduledFeeChange<Hash, Balance, BlockNumber>> {
+			Fees::scheduled_fee_changes()
+		}
+
+		fn estimate_commit_fee(stored_until_date: Moment) -> Result<FeeEstimate<Balance>, DispatchError> {
+			let state_rent = Anchor::commit_state_rent(stored_until_date)?;
+			// the hashes do not change the cost of the call
+			let call = Call::Anchor(anchor::Call::commit(Hash::default(), Hash::default(), Hash::default(), stored_until_date));
+			Ok(impls::estimate_fee(call, state_rent, 0))
+		}
+
+		fn estimate_nft_mint_fee(anchor_id: Hash, deposit_address: [u8; 20], proofs: Vec<proofs::Proof>, document_layout: DocumentLayoutVersion, static_proofs: Vec<Hash>, dest_id: chainbridge::ChainId) -> Result<FeeEstimate<Balance>, DispatchError> {
+			let call_fee = Fees::price_of(Nfts::fee_key()).ok_or("fee not found for key")?;
+			let call = Call::Nfts(nfts::Call::validate_mint(anchor_id, deposit_address, proofs, document_layout, static_proofs, dest_id));
+			Ok(impls::estimate_fee(call, 0, call_fee))
+		}
+
+		fn estimate_native_transfer_fee(amount: Balance, recipient: Vec<u8>, dest_id: chainbridge::ChainId) -> FeeEstimate<Balance> {
+			let call_fee = PalletBridge::token_transfer_fee();
+			let call = Call::PalletBridge(pallet_bridge::Call::transfer_native(amount, recipient, dest_id));
+			impls::estimate_fee(call, 0, call_fee)
+		}
+
+		fn estimate_nft_transfer_fee(recipient: Vec<u8>, from_registry: RegistryId, token_id: TokenId, dest_id: chainbridge::ChainId) -> Result<FeeEstimate<Balance>, DispatchError> {
+			let call_fee = Fees::price_of(PalletBridge::nft_fee_key()).ok_or("fee not found for key")?;
+			let call = Call::PalletBridge(pallet_bridge::Call::transfer_asset(recipient, from_registry, token_id, dest_id));
+			Ok(impls::estimate_fee(call, 0, call_fee))
+		}
+
+		fn sponsorship(account: AccountId) -> Option<Sponsorship<AccountId, Balance, BlockNumber>> {
+			Fees::sponsorship(account)
 		}
 	}
 
@@ -1141,6 +1351,7 @@ impl_runtime_apis! {
             //let params = (&config, &whitelist);
             let params = (&pallet, &benchmark, &lowest_range_values, &highest_range_values, &steps, repeat, &whitelist);
 
+            add_benchmark!(params, batches, anchor, Anchor);
             add_benchmark!(params, batches, va_registry, Registry);
 
             if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
@@ -1162,4 +1373,26 @@ mod tests {
 
         is_submit_signed_transaction::<Runtime>();
     }
+
+    #[test]
+    fn check_proofs_limits_rejects_oversized_mints() {
+        use frame_support::weights::GetDispatchInfo;
+        use sp_runtime::traits::SignedExtension;
+
+        let validate_mint = |hashes: u32| {
+            let proof = proofs::Proof::new(Default::default(), vec![Default::default(); hashes as usize]);
+            let call = Call::Nfts(nfts::Call::validate_mint(
+                Default::default(),
+                [0; 20],
+                vec![proof],
+                proofs::DOCUMENT_LAYOUT_V1,
+                vec![],
+                0,
+            ));
+            impls::CheckProofsLimits.validate(&AccountId::default(), &call, &call.get_dispatch_info(), 0)
+        };
+
+        assert!(validate_mint(MaxProofHashes::get()).is_ok());
+        assert!(validate_mint(MaxProofHashes::get() + 1).is_err());
+    }
 }
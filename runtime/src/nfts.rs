@@ -1,17 +1,24 @@
 use crate::bridge as pallet_bridge;
-use crate::{anchor, fees, proofs, proofs::Proof};
-use frame_support::{decl_event, decl_module, dispatch::DispatchResult, ensure, traits::Get};
+use crate::{anchor, fees, proofs, proofs::{DocumentLayout, DocumentLayoutVersion, Proof, ProofSize}};
+use frame_support::{decl_error, decl_event, decl_module, dispatch::DispatchResult, traits::Get, weights::Weight};
 use frame_system::ensure_signed;
 use sp_core::H256;
 use sp_std::vec::Vec;
 use crate::constants::currency;
-use sp_runtime::traits::SaturatedConversion;
+use sp_runtime::traits::{Hash, SaturatedConversion};
 
-/// Additional Fee charged to validate NFT proofs
-const NFT_FEE: u128 = 10 * currency::CFG;
+/// Initial price of the fee charged to validate NFT proofs, burnt unless its distribution is
+/// changed. The price can be changed with `fees::set_fee`, see `Module::fee_key`.
+pub const NFT_FEE: u128 = 10 * currency::CFG;
 
 pub trait Trait: anchor::Trait + pallet_balances::Trait + pallet_bridge::Trait {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+    /// Maximum number of proofs of a `validate_mint` call.
+    type MaxProofs: Get<u32>;
+
+    /// Maximum number of hashes of each proof of a `validate_mint` call.
+    type MaxProofHashes: Get<u32>;
 }
 
 decl_event!(
@@ -20,8 +27,28 @@ decl_event!(
     }
 );
 
+decl_error! {
+    pub enum Error for Module<T: Trait> {
+        /// No proofs were provided.
+        EmptyProofs,
+        /// The document layout version is not known.
+        UnknownDocumentLayout,
+        /// The number of static proofs does not match the document layout.
+        StaticProofsMismatch,
+        /// The document root computed from the static proofs does not match the anchored one.
+        DocumentRootMismatch,
+        /// One of the proofs provided does not lead to the document root.
+        InvalidProof,
+        /// More proofs were provided than allowed by `MaxProofs`.
+        TooManyProofs,
+        /// One of the proofs provided has more hashes than allowed by `MaxProofHashes`.
+        ProofTooLong,
+    }
+}
+
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin , T: pallet_bridge::Trait {
+        type Error = Error<T>;
         fn deposit_event() = default;
 
         /// Validates the proofs provided against the document root associated with the anchor_id.
@@ -30,17 +57,20 @@ decl_module! {
         ///
         /// Adds additional fee to compensate the current cost of target chains
         /// # <weight>
-        /// - depends on the arguments
+        /// - depends on the number of proofs and the number of hashes of the longest one
         /// # </weight>
-        #[weight = 120_000_000]
-        fn validate_mint(origin, anchor_id: T::Hash, deposit_address: [u8; 20], pfs: Vec<Proof>, static_proofs: [H256;3], dest_id: chainbridge::ChainId) -> DispatchResult {
+        #[weight = Module::<T>::validate_mint_weight(pfs)]
+        fn validate_mint(origin, anchor_id: T::Hash, deposit_address: [u8; 20], pfs: Vec<Proof>, document_layout: DocumentLayoutVersion, static_proofs: Vec<H256>, dest_id: chainbridge::ChainId) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
+            // reject oversized proofs before reading any state
+            Self::check_proofs_limits(&pfs)?;
+
             // get the anchor data from anchor ID
-            let anchor_data = <anchor::Module<T>>::get_anchor_by_id(anchor_id).ok_or("Anchor doesn't exist")?;
+            let anchor_data = <anchor::Module<T>>::get_anchor_by_id(anchor_id)?.ok_or("Anchor doesn't exist")?;
 
             // validate proofs
-            ensure!(Self::validate_proofs(anchor_data.doc_root, &pfs, static_proofs), "Invalid proofs");
+            Self::validate_proofs(anchor_data.doc_root, &pfs, document_layout, &static_proofs)?;
 
             // get the bundled hash
             let bundled_hash = Self::get_bundled_hash(pfs, deposit_address);
@@ -49,9 +79,8 @@ decl_module! {
             let metadata = bundled_hash.as_ref().to_vec();
             let resource_id = <T as pallet_bridge::Trait>::HashId::get();
 
-			// Burn additional fees
-			let nft_fee: T::Balance = NFT_FEE.saturated_into();
-            <fees::Module<T>>::burn_fee(&who, nft_fee)?;
+            // Pay additional fees
+            <fees::Module<T>>::pay_fee(who, Self::fee_key())?;
 
             <chainbridge::Module<T>>::transfer_generic(dest_id, resource_id, metadata)?;
             Ok(())
@@ -60,10 +89,70 @@ decl_module! {
 }
 
 impl<T: Trait + pallet_bridge::Trait> Module<T> {
-    /// Validates the proofs again the provided doc_root.
-    /// returns false if any proofs are invalid.
-    fn validate_proofs(doc_root: T::Hash, pfs: &Vec<Proof>, static_proofs: [H256; 3]) -> bool {
-        proofs::validate_proofs(H256::from_slice(doc_root.as_ref()), pfs, static_proofs)
+    /// Key of the fee charged by `validate_mint`.
+    pub fn fee_key() -> T::Hash {
+        T::Hashing::hash_of(&(b"nfts", b"validate_mint"))
+    }
+
+    /// Fee keys of this module to register in the fees module.
+    pub fn fee_key_registrations() -> Vec<fees::FeeKeyRegistration<T::Hash, T::Balance>> {
+        sp_std::vec![fees::FeeKeyRegistration {
+            key: Self::fee_key(),
+            info: fees::FeeKeyInfo {
+                name: b"nft_validate_mint".to_vec(),
+                unit: fees::FeeUnit::PerCall,
+                pallet: b"nfts".to_vec(),
+            },
+            initial_price: Some(NFT_FEE.saturated_into()),
+            initial_distribution: Some(fees::FeeDistribution::burn_all()),
+        }]
+    }
+
+    /// Computation weight of `validate_mint`: a base weight, the hashing of the leaf of each
+    /// proof and of the hashes of each proof, up to the longest one. The sizes are not capped,
+    /// so that oversized proofs are weighed by their actual size until they are rejected.
+    fn validate_mint_weight(pfs: &[Proof]) -> Weight {
+        let depth = pfs.iter().map(|p| p.hashes_len() as Weight).max().unwrap_or_default();
+        (120_000_000 as Weight)
+            .saturating_add((2_000_000 as Weight).saturating_mul(pfs.len() as Weight))
+            .saturating_add((1_500_000 as Weight).saturating_mul((pfs.len() as Weight).saturating_mul(depth)))
+    }
+
+    /// Checks the number of proofs and of hashes of each proof against `MaxProofs` and
+    /// `MaxProofHashes`. Only the sizes of the proofs are checked, no state is read.
+    pub fn check_proofs_limits(pfs: &[Proof]) -> DispatchResult {
+        proofs::check_proofs_limits(pfs, T::MaxProofs::get(), T::MaxProofHashes::get())
+            .map_err(Self::proofs_error)
+    }
+
+    /// Validates the proofs again the provided doc_root, using the given document layout.
+    /// returns an error describing the failure if any proofs are invalid.
+    fn validate_proofs(doc_root: T::Hash, pfs: &Vec<Proof>, document_layout: DocumentLayoutVersion, static_proofs: &[H256]) -> DispatchResult {
+        let layout = DocumentLayout::from_version(document_layout).ok_or(Error::<T>::UnknownDocumentLayout)?;
+        // proofs are hashed with the default hash function unless they have their own
+        proofs::validate_proofs(H256::from_slice(doc_root.as_ref()), pfs, &layout, static_proofs, Default::default())
+            .map_err(Self::proofs_error)
+    }
+
+    /// Returns the error of this module describing why proofs are not valid.
+    fn proofs_error(e: proofs::ProofsError) -> sp_runtime::DispatchError {
+        match e {
+            proofs::ProofsError::AnchorNotFound => "Anchor doesn't exist".into(),
+            proofs::ProofsError::EmptyProofs => Error::<T>::EmptyProofs.into(),
+            proofs::ProofsError::UnknownDocumentLayout => Error::<T>::UnknownDocumentLayout.into(),
+            proofs::ProofsError::StaticProofsMismatch => Error::<T>::StaticProofsMismatch.into(),
+            proofs::ProofsError::DocumentRootMismatch => Error::<T>::DocumentRootMismatch.into(),
+            proofs::ProofsError::InvalidProof(_) => Error::<T>::InvalidProof.into(),
+            proofs::ProofsError::TooManyProofs => Error::<T>::TooManyProofs.into(),
+            proofs::ProofsError::ProofTooLong(_) => Error::<T>::ProofTooLong.into(),
+            // multiproofs and proof fields are not validated here
+            proofs::ProofsError::MalformedMultiProof | proofs::ProofsError::InvalidMultiProof => {
+                Error::<T>::InvalidProof.into()
+            }
+            proofs::ProofsError::ProofFieldTooLong(_) | proofs::ProofsError::MultiProofTooLong => {
+                Error::<T>::ProofTooLong.into()
+            }
+        }
     }
 
     /// Returns a Keccak hash of deposit_address + hash(keccak(name+value+salt)) of each proof provided.
@@ -87,7 +176,7 @@ mod tests {
     use codec::Encode;
     use frame_support::{
         assert_err, assert_ok, dispatch::DispatchError, ord_parameter_types, parameter_types,
-        weights::Weight,
+        weights::{GetDispatchInfo, Weight},
     };
     //use chainbridge::ResourceId;
     use frame_system::EnsureSignedBy;
@@ -155,10 +244,36 @@ mod tests {
         type SystemWeightInfo = ();
     }
 
-    impl anchor::Trait for Test {}
+    parameter_types! {
+        pub const PreCommitExpirationDurationBlocks: u64 = 800;
+        pub const PreCommitEvictionBucketMultiplier: u32 = 5;
+        pub const MaxLoopInTx: u64 = 500;
+        pub const StorageMaxDays: u32 = 376200;
+        pub const MaxEvictedAnchorProofNodes: u32 = 16;
+        pub const MaxEvictedAnchorProofSize: u32 = 16 * 1024;
+    }
+
+    impl anchor::Trait for Test {
+        type Event = ();
+        type WeightInfo = ();
+        type PreCommitExpirationDurationBlocks = PreCommitExpirationDurationBlocks;
+        type PreCommitEvictionBucketMultiplier = PreCommitEvictionBucketMultiplier;
+        type MaxLoopInTx = MaxLoopInTx;
+        type StorageMaxDays = StorageMaxDays;
+        type MaxEvictedAnchorProofNodes = MaxEvictedAnchorProofNodes;
+        type MaxEvictedAnchorProofSize = MaxEvictedAnchorProofSize;
+        type AdminOrigin = frame_system::EnsureRoot<Self::AccountId>;
+    }
+
+    parameter_types! {
+        pub const MaxProofs: u32 = 4;
+        pub const MaxProofHashes: u32 = 4;
+    }
 
     impl Trait for Test {
         type Event = ();
+        type MaxProofs = MaxProofs;
+        type MaxProofHashes = MaxProofHashes;
     }
 
     parameter_types! {
@@ -212,9 +327,18 @@ mod tests {
         type EventHandler = ();
     }
 
+    parameter_types! {
+        pub FeeKeyRegistrations: Vec<fees::FeeKeyRegistration<H256, u128>> = Nfts::fee_key_registrations();
+    }
+
     impl fees::Trait for Test {
         type Event = ();
         type FeeChangeOrigin = frame_system::EnsureRoot<u64>;
+        type EvictionBountyPoolAccount = ();
+        type DefaultFeeDistribution = ();
+        type FeeKeyRegistrations = FeeKeyRegistrations;
+        type MinFeeChangeNotice = ();
+        type SponsoredFeeKeys = ();
     }
 
     parameter_types! {
@@ -262,7 +386,7 @@ mod tests {
         t.into()
     }
 
-    fn get_invalid_proof() -> (Proof, H256, [H256; 3]) {
+    fn get_invalid_proof() -> (Proof, H256, Vec<H256>) {
         let proof = Proof::new(
             [
                 1, 93, 41, 93, 124, 185, 25, 20, 141, 93, 101, 68, 16, 11, 142, 219, 3, 124, 155,
@@ -289,7 +413,7 @@ mod tests {
         ]
         .into();
 
-        let static_proofs: [H256; 3] = [
+        let static_proofs: Vec<H256> = vec![
             [
                 25, 102, 189, 46, 86, 242, 48, 217, 254, 16, 20, 211, 98, 206, 125, 92, 167, 175,
                 70, 161, 35, 135, 33, 80, 225, 247, 4, 240, 138, 86, 167, 142,
@@ -310,7 +434,7 @@ mod tests {
         (proof, doc_root, static_proofs)
     }
 
-    fn get_valid_proof() -> (Proof, sp_core::H256, [H256; 3]) {
+    fn get_valid_proof() -> (Proof, sp_core::H256, Vec<H256>) {
         let proof = Proof::new(
             [
                 1, 93, 41, 93, 124, 185, 25, 20, 141, 93, 101, 68, 16, 11, 142, 219, 3, 124, 155,
@@ -347,7 +471,7 @@ mod tests {
         ]
         .into();
 
-        let static_proofs: [H256; 3] = [
+        let static_proofs: Vec<H256> = vec![
             [
                 25, 102, 189, 46, 86, 242, 48, 217, 254, 16, 20, 211, 98, 206, 125, 92, 167, 175,
                 70, 161, 35, 135, 33, 80, 225, 247, 4, 240, 138, 86, 167, 142,
@@ -372,13 +496,13 @@ mod tests {
         sp_core::H256,
         [u8; 20],
         Vec<Proof>,
-        [H256; 3],
+        Vec<H256>,
         chainbridge::ChainId,
     ) {
         let anchor_id = <Test as frame_system::Trait>::Hashing::hash_of(&0);
         let deposit_address: [u8; 20] = [0; 20];
         let pfs: Vec<Proof> = vec![];
-        let static_proofs: [H256; 3] = [[0; 32].into(), [0; 32].into(), [0; 32].into()];
+        let static_proofs: Vec<H256> = vec![[0; 32].into(), [0; 32].into(), [0; 32].into()];
         let chain_id: chainbridge::ChainId = 1;
         (anchor_id, deposit_address, pfs, static_proofs, chain_id)
     }
@@ -393,6 +517,7 @@ mod tests {
                     anchor_id,
                     deposit_address,
                     pfs,
+                    proofs::DOCUMENT_LAYOUT_V1,
                     static_proofs,
                     chain_id
                 ),
@@ -411,6 +536,7 @@ mod tests {
                     anchor_id,
                     deposit_address,
                     pfs,
+                    proofs::DOCUMENT_LAYOUT_V1,
                     static_proofs,
                     chain_id
                 ),
@@ -440,10 +566,93 @@ mod tests {
                     anchor_id,
                     deposit_address,
                     vec![pf],
+                    proofs::DOCUMENT_LAYOUT_V1,
+                    static_proofs,
+                    0
+                ),
+                Error::<Test>::InvalidProof
+            );
+        })
+    }
+
+    #[test]
+    fn doc_root_mismatch() {
+        new_test_ext().execute_with(|| {
+            let deposit_address: [u8; 20] = [0; 20];
+            let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+            let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+            let (pf, _, static_proofs) = get_valid_proof();
+            assert_ok!(Anchor::commit(
+                Origin::signed(2),
+                pre_image,
+                <Test as frame_system::Trait>::Hashing::hash_of(&1),
+                <Test as frame_system::Trait>::Hashing::hash_of(&0),
+                common::MS_PER_DAY + 1
+            ));
+
+            assert_err!(
+                Nfts::validate_mint(
+                    Origin::signed(USER_A),
+                    anchor_id,
+                    deposit_address,
+                    vec![pf],
+                    proofs::DOCUMENT_LAYOUT_V1,
                     static_proofs,
                     0
                 ),
-                "Invalid proofs"
+                Error::<Test>::DocumentRootMismatch
+            );
+        })
+    }
+
+    #[test]
+    fn proofs_over_limits() {
+        new_test_ext().execute_with(|| {
+            let (anchor_id, deposit_address, _, static_proofs, chain_id) = get_params();
+            let (pf, _, _) = get_valid_proof();
+
+            // proofs are weighed by their number and the longest one, oversized ones included
+            let weight = |pfs| {
+                nfts::Call::<Test>::validate_mint(
+                    anchor_id,
+                    deposit_address,
+                    pfs,
+                    proofs::DOCUMENT_LAYOUT_V1,
+                    static_proofs.clone(),
+                    chain_id,
+                )
+                .get_dispatch_info()
+                .weight
+            };
+            let long_pf = Proof::new(pf.leaf_hash, vec![H256::zero(); 5]);
+            assert!(weight(vec![pf.clone(); 5]) > weight(vec![pf.clone(); 4]));
+            assert!(weight(vec![pf.clone(), long_pf.clone()]) > weight(vec![pf.clone(); 2]));
+
+            // the limits are checked before the anchor is looked up
+            assert_err!(
+                Nfts::validate_mint(
+                    Origin::signed(USER_A),
+                    anchor_id,
+                    deposit_address,
+                    vec![pf.clone(); 5],
+                    proofs::DOCUMENT_LAYOUT_V1,
+                    static_proofs.clone(),
+                    chain_id
+                ),
+                Error::<Test>::TooManyProofs
+            );
+
+            assert_err!(
+                Nfts::validate_mint(
+                    Origin::signed(USER_A),
+                    anchor_id,
+                    deposit_address,
+                    vec![pf, long_pf],
+                    proofs::DOCUMENT_LAYOUT_V1,
+                    static_proofs,
+                    chain_id
+                ),
+                Error::<Test>::ProofTooLong
             );
         })
     }
@@ -471,6 +680,7 @@ mod tests {
                     anchor_id,
                     deposit_address,
                     vec![pf],
+                    proofs::DOCUMENT_LAYOUT_V1,
                     static_proofs,
                     0
                 ),
@@ -505,6 +715,7 @@ mod tests {
                 anchor_id,
                 deposit_address,
                 vec![pf],
+                proofs::DOCUMENT_LAYOUT_V1,
                 static_proofs,
                 0
             ),);
@@ -514,4 +725,40 @@ mod tests {
             assert_eq!(account_current_balance, 90 * currency::CFG);
         })
     }
+
+    #[test]
+    fn valid_proof_pays_fee_set() {
+        new_test_ext().execute_with(|| {
+            let dest_id = 0;
+            let deposit_address: [u8; 20] = [0; 20];
+            let pre_image = <Test as frame_system::Trait>::Hashing::hash_of(&0);
+            let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+            let (pf, doc_root, static_proofs) = get_valid_proof();
+            assert_ok!(Anchor::commit(
+                Origin::signed(2),
+                pre_image,
+                doc_root,
+                <Test as frame_system::Trait>::Hashing::hash_of(&0),
+                common::MS_PER_DAY + 1
+            ));
+
+            // the fee is registered with its initial price and can be changed
+            assert_eq!(<fees::Module<Test>>::price_of(Nfts::fee_key()), Some(NFT_FEE));
+            assert_ok!(<fees::Module<Test>>::set_fee(Origin::root(), Nfts::fee_key(), 5 * currency::CFG));
+
+            assert_ok!(ChainBridge::whitelist_chain(Origin::root(), dest_id.clone()));
+            assert_ok!(Nfts::validate_mint(
+                Origin::signed(USER_A),
+                anchor_id,
+                deposit_address,
+                vec![pf],
+                proofs::DOCUMENT_LAYOUT_V1,
+                static_proofs,
+                0
+            ),);
+
+            let account_current_balance = <pallet_balances::Module<Test>>::free_balance(USER_A);
+            assert_eq!(account_current_balance, 95 * currency::CFG);
+        })
+    }
 }
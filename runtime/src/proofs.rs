@@ -1,13 +1,19 @@
 use codec::{Decode, Encode};
 use sp_core::H256;
-use sp_std::vec::Vec;
+use sp_runtime::RuntimeDebug;
+use sp_std::{collections::btree_set::BTreeSet, vec::Vec};
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 #[cfg_attr(not(feature = "std"), derive(sp_runtime::RuntimeDebug))]
-#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
 pub struct Proof {
     pub leaf_hash: H256,
     sorted_hashes: Vec<H256>,
+    /// Hash function of the nodes of this proof, overriding the one given to the validation.
+    pub hasher: Option<HashFunction>,
 }
 
 impl Proof {
@@ -15,14 +21,157 @@ impl Proof {
         Self {
             leaf_hash: hash,
             sorted_hashes,
+            hasher: None,
         }
     }
+
+    /// Sets the hash function of the nodes of this proof.
+    pub fn with_hasher(mut self, hasher: Option<HashFunction>) -> Self {
+        self.hasher = hasher;
+        self
+    }
 }
 
-/// Validates each proof and return true if all the proofs are valid else returns false
+/// Proof of several leaves of a merkle tree of sorted hashes at once, in the format of the
+/// OpenZeppelin multiproofs.
 ///
-/// This is an optimized Merkle proof checker. It caches all valid leaves in an array called
-/// matches. If a proof is validated, all the intermediate hashes will be added to the array.
+/// The root is rebuilt by hashing pairs of nodes, one pair per flag. The first node of a pair is
+/// the next leaf, or the next computed node once all the leaves are used. The second node is
+/// taken the same way if the flag is set, else it is the next of the `hashes`. Each node is thus
+/// sent once, however many of the proven leaves it is shared by.
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct MultiProof {
+    /// Proven leaves, in the order they are consumed when rebuilding the root.
+    pub leaves: Vec<H256>,
+    /// Nodes of the tree which are not computed from the leaves.
+    pub hashes: Vec<H256>,
+    /// Whether the second node of each pair is a leaf or a computed node rather than one of
+    /// the `hashes`.
+    pub flags: Vec<bool>,
+}
+
+/// Reason why a set of proofs is not valid.
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum ProofsError {
+    /// No anchor exists for the given anchor id.
+    AnchorNotFound,
+    /// The list of proofs is empty.
+    EmptyProofs,
+    /// The document root computed from the static proofs does not match the anchored one.
+    DocumentRootMismatch,
+    /// The document layout version is not known.
+    UnknownDocumentLayout,
+    /// The number of static proofs does not match the document layout.
+    StaticProofsMismatch,
+    /// The proof at the given index does not lead to any of the already proven nodes.
+    /// When several proofs are invalid, this is the index of the first one.
+    InvalidProof(u32),
+    /// The number of leaves, hashes and flags of the multiproof do not match.
+    MalformedMultiProof,
+    /// The root of the multiproof is not any of the already proven nodes.
+    InvalidMultiProof,
+    /// There are more proofs than allowed.
+    TooManyProofs,
+    /// The proof at the given index has more hashes than allowed.
+    ProofTooLong(u32),
+    /// The value or the property of the proof at the given index is longer than allowed.
+    ProofFieldTooLong(u32),
+    /// The multiproof has more hashes or flags than its leaves may require.
+    MultiProofTooLong,
+}
+
+/// Result of validating a set of proofs without dispatching a call, see [validate_each_proof].
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct ProofsValidation {
+    /// Document root computed from the static proofs.
+    pub doc_root: H256,
+    /// Validity of each proof, in the order they were given.
+    pub valid_proofs: Vec<bool>,
+    /// Reason why the proofs are not valid, `None` if they are.
+    pub error: Option<ProofsError>,
+}
+
+/// Version of a document tree layout, see [DocumentLayout::from_version].
+pub type DocumentLayoutVersion = u8;
+
+/// Layout of documents with a basic data, a zk data and a signature root.
+pub const DOCUMENT_LAYOUT_V1: DocumentLayoutVersion = 1;
+
+/// Hash function used to combine the nodes of a document tree.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum HashFunction {
+    Blake2_256,
+    /// Keccak 256 as used by Ethereum, not the finalized SHA3 256.
+    Keccak256,
+    Sha2_256,
+}
+
+impl Default for HashFunction {
+    fn default() -> Self {
+        HashFunction::Blake2_256
+    }
+}
+
+impl HashFunction {
+    /// Hashes the given data.
+    pub fn hash(&self, data: &[u8]) -> H256 {
+        match self {
+            HashFunction::Blake2_256 => sp_io::hashing::blake2_256(data).into(),
+            HashFunction::Keccak256 => sp_io::hashing::keccak_256(data).into(),
+            HashFunction::Sha2_256 => sp_io::hashing::sha2_256(data).into(),
+        }
+    }
+}
+
+/// Describes how the static roots of a document combine into its document root.
+///
+/// The static roots are the first nodes of the tree, in the order they are given. Each
+/// combination hashes two existing nodes into a new node, the last node being the document root.
+#[derive(Clone, PartialEq, RuntimeDebug)]
+pub struct DocumentLayout {
+    /// Number of static roots of the document.
+    pub static_roots: u32,
+    /// Pairs of node indexes combined into a new node, in order.
+    pub combinations: &'static [(u32, u32)],
+    /// Whether the two nodes of a combination are sorted before hashing.
+    pub sorted: bool,
+    /// Hash function used for the combinations.
+    pub hasher: HashFunction,
+}
+
+impl DocumentLayout {
+    /// Returns the layout of the given version, `None` if the version is not known.
+    ///
+    /// Version 1:
+    /// ```text
+    ///                      DocumentRoot (4)
+    ///                      /          \
+    ///        Signing Root (3)          Signature Root (2)
+    ///          /          \
+    ///   data root 1 (0)   data root 2 (1)
+    /// ```
+    pub fn from_version(version: DocumentLayoutVersion) -> Option<Self> {
+        match version {
+            DOCUMENT_LAYOUT_V1 => Some(DocumentLayout {
+                static_roots: 3,
+                combinations: &[(0, 1), (3, 2)],
+                sorted: false,
+                hasher: HashFunction::Blake2_256,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Validates each proof and returns `Ok(())` if all the proofs are valid, else returns the
+/// reason of the failure.
+///
+/// This is an optimized Merkle proof checker. It caches all valid leaves in a set called
+/// matches. If a proof is validated, all the intermediate hashes will be added to the set.
 /// When validating a subsequent proof, that proof will stop being validated as soon as a hash
 /// has been computed that has been a computed hash in a previously validated proof.
 ///
@@ -30,28 +179,209 @@ impl Proof {
 /// nodes when submitting multiple proofs.
 ///
 /// matches: matches will have a pre computed hashes provided by the client and document root of the
-/// reference anchor. static proofs are used to computed the pre computed hashes and the result is
-/// checked against document root provided.
-pub fn validate_proofs(doc_root: H256, proofs: &Vec<Proof>, static_proofs: [H256; 3]) -> bool {
+/// reference anchor. static proofs are combined as described by the `layout` to compute the pre
+/// computed hashes and the result is checked against document root provided.
+///
+/// The nodes of each proof are hashed with `hasher`, unless the proof has its own hash function.
+///
+/// Complexity: with `n` proofs of `h` hashes in total, at most `h` nodes are hashed and the
+/// cache never holds more than `2h` nodes besides the static ones, so the validation takes
+/// `O(h)` hashes and `O((n + h) log h)` cache lookups and inserts in the worst case.
+pub fn validate_proofs(
+    doc_root: H256,
+    proofs: &Vec<Proof>,
+    layout: &DocumentLayout,
+    static_proofs: &[H256],
+    hasher: HashFunction,
+) -> Result<(), ProofsError> {
     if proofs.len() < 1 {
-        return false;
+        return Err(ProofsError::EmptyProofs);
+    }
+
+    let (calc_doc_root, mut matches) =
+        pre_matches(layout, static_proofs).ok_or(ProofsError::StaticProofsMismatch)?;
+    if calc_doc_root != doc_root {
+        return Err(ProofsError::DocumentRootMismatch);
+    }
+
+    match proofs.iter().position(|proof| {
+        !validate_proof(
+            &mut matches,
+            proof.leaf_hash,
+            &proof.sorted_hashes,
+            proof.hasher.unwrap_or(hasher),
+        )
+    }) {
+        Some(index) => Err(ProofsError::InvalidProof(index as u32)),
+        None => Ok(()),
+    }
+}
+
+/// Validates the proofs against `doc_root` like [validate_proofs], but reports the validity of
+/// each proof, the document root computed from the static proofs and the reason of a failure.
+/// Each proof is validated against the static proofs, even if they do not match `doc_root`.
+///
+/// Only the nodes of valid proofs are cached for the subsequent proofs, so an invalid proof
+/// cannot make the nodes it claims count as proven.
+pub fn validate_each_proof(
+    doc_root: H256,
+    proofs: &Vec<Proof>,
+    layout: &DocumentLayout,
+    static_proofs: &[H256],
+    hasher: HashFunction,
+) -> ProofsValidation {
+    let computed = pre_matches(layout, static_proofs);
+    let static_proofs_match = computed.is_some();
+    let (computed_doc_root, mut matches) = computed.unwrap_or_default();
+    let valid_proofs: Vec<bool> = proofs
+        .iter()
+        .map(|proof| {
+            let mut proof_matches = matches.clone();
+            let valid = validate_proof(
+                &mut proof_matches,
+                proof.leaf_hash,
+                &proof.sorted_hashes,
+                proof.hasher.unwrap_or(hasher),
+            );
+            if valid {
+                matches = proof_matches;
+            }
+            valid
+        })
+        .collect();
+
+    let error = if proofs.is_empty() {
+        Some(ProofsError::EmptyProofs)
+    } else if !static_proofs_match {
+        Some(ProofsError::StaticProofsMismatch)
+    } else if computed_doc_root != doc_root {
+        Some(ProofsError::DocumentRootMismatch)
+    } else {
+        valid_proofs
+            .iter()
+            .position(|valid| !valid)
+            .map(|index| ProofsError::InvalidProof(index as u32))
+    };
+
+    ProofsValidation {
+        doc_root: computed_doc_root,
+        valid_proofs,
+        error,
+    }
+}
+
+/// Validates a multiproof against `doc_root` and returns `Ok(())` if it is valid, else returns the
+/// reason of the failure.
+///
+/// The static proofs are combined as described by the `layout` like for [validate_proofs], and
+/// the root of the multiproof, hashed with `hasher`, must be one of the resulting nodes.
+///
+/// Complexity: a multiproof of `f` flags is validated with `f` hashes, each node being hashed
+/// once however many of the leaves share it.
+pub fn validate_multi_proof(
+    doc_root: H256,
+    multi_proof: &MultiProof,
+    layout: &DocumentLayout,
+    static_proofs: &[H256],
+    hasher: HashFunction,
+) -> Result<(), ProofsError> {
+    if multi_proof.leaves.is_empty() {
+        return Err(ProofsError::EmptyProofs);
+    }
+
+    let (calc_doc_root, matches) =
+        pre_matches(layout, static_proofs).ok_or(ProofsError::StaticProofsMismatch)?;
+    if calc_doc_root != doc_root {
+        return Err(ProofsError::DocumentRootMismatch);
+    }
+
+    let root = multi_proof_root(multi_proof, hasher).ok_or(ProofsError::MalformedMultiProof)?;
+    if !matches.contains(&root) {
+        return Err(ProofsError::InvalidMultiProof);
+    }
+
+    Ok(())
+}
+
+/// Sizes of a proof, which are checked by [check_proofs_limits] before the proof is hashed.
+pub trait ProofSize {
+    /// Number of hashes from the leaf of the proof up to the root.
+    fn hashes_len(&self) -> usize;
+}
+
+impl ProofSize for Proof {
+    fn hashes_len(&self) -> usize {
+        self.sorted_hashes.len()
+    }
+}
+
+/// Sizes of a multiproof, which are checked by [check_multi_proof_limits] before the multiproof
+/// is hashed.
+pub trait MultiProofSize {
+    /// Number of proven leaves.
+    fn leaves_len(&self) -> usize;
+    /// Number of nodes which are not computed from the leaves.
+    fn hashes_len(&self) -> usize;
+    /// Number of pairs of nodes hashed to rebuild the root.
+    fn flags_len(&self) -> usize;
+}
+
+impl MultiProofSize for MultiProof {
+    fn leaves_len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    fn hashes_len(&self) -> usize {
+        self.hashes.len()
     }
 
-    let (valid, mut matches) = pre_matches(static_proofs, doc_root);
-    if !valid {
-        return false;
+    fn flags_len(&self) -> usize {
+        self.flags.len()
     }
+}
 
-    return proofs
+/// Checks the number of proofs and the number of hashes of each proof against the given
+/// maximums. This only looks at the sizes of the proofs and is meant to reject oversized
+/// proofs before any of them is hashed.
+pub fn check_proofs_limits<P: ProofSize>(
+    proofs: &[P],
+    max_proofs: u32,
+    max_hashes: u32,
+) -> Result<(), ProofsError> {
+    if proofs.len() > max_proofs as usize {
+        return Err(ProofsError::TooManyProofs);
+    }
+
+    match proofs
         .iter()
-        .map(|proof| validate_proof(&mut matches, proof.leaf_hash, proof.sorted_hashes.clone()))
-        .fold(true, |acc, b| acc && b);
+        .position(|proof| proof.hashes_len() > max_hashes as usize)
+    {
+        Some(index) => Err(ProofsError::ProofTooLong(index as u32)),
+        None => Ok(()),
+    }
 }
 
-// computes blake2 256 sorted hash of the a and b
-// if a < b: blake256(a+b)
-// else: blake256(b+a)
-fn sort_hash_of(a: H256, b: H256) -> H256 {
+/// Checks the size of a multiproof against the given maximums, like [check_proofs_limits].
+/// Each leaf needs at most `max_hashes` nodes to reach the root, so a multiproof needs at most
+/// as many hashes and flags as `max_hashes` per leaf.
+pub fn check_multi_proof_limits<M: MultiProofSize>(
+    multi_proof: &M,
+    max_proofs: u32,
+    max_hashes: u32,
+) -> Result<(), ProofsError> {
+    if multi_proof.leaves_len() > max_proofs as usize {
+        return Err(ProofsError::TooManyProofs);
+    }
+
+    let max_nodes = multi_proof.leaves_len().saturating_mul(max_hashes as usize);
+    if multi_proof.hashes_len() > max_nodes || multi_proof.flags_len() > max_nodes {
+        return Err(ProofsError::MultiProofTooLong);
+    }
+
+    Ok(())
+}
+
+fn sort_hash_of(hasher: HashFunction, a: H256, b: H256) -> H256 {
     let mut h: Vec<u8> = Vec::with_capacity(64);
     if a < b {
         h.extend_from_slice(&a[..]);
@@ -61,65 +391,117 @@ fn sort_hash_of(a: H256, b: H256) -> H256 {
         h.extend_from_slice(&a[..]);
     }
 
-    sp_io::hashing::blake2_256(&h).into()
+    hasher.hash(&h)
 }
 
-// computes blake2 256 hash of the a + b
-fn hash_of(a: H256, b: H256) -> H256 {
+// computes the hash of the a + b, sorted if required, with the hash function of the layout
+fn layout_hash_of(layout: &DocumentLayout, a: H256, b: H256) -> H256 {
+    let (a, b) = if layout.sorted && b < a {
+        (b, a)
+    } else {
+        (a, b)
+    };
     let mut h: Vec<u8> = Vec::with_capacity(64);
     h.extend_from_slice(&a[..]);
     h.extend_from_slice(&b[..]);
-    sp_io::hashing::blake2_256(&h).into()
+    layout.hasher.hash(&h)
 }
 
 // validates the proof by computing a sorted hash of the provided proofs with hash as initial value.
+// the nodes are hashed with the given hash function.
 // each calculated hash is memoized.
 // Validation stops as soon as the any computed hash is found in the matches.
 // if no computed hash is found in the matches, validation fails.
-fn validate_proof(matches: &mut Vec<H256>, hash: H256, proofs: Vec<H256>) -> bool {
+// each step is a lookup and at most two inserts in the matches, each O(log m) for m matches.
+fn validate_proof(
+    matches: &mut BTreeSet<H256>,
+    hash: H256,
+    proofs: &[H256],
+    hasher: HashFunction,
+) -> bool {
     // if hash is already cached earlier
     if matches.contains(&hash) {
         return true;
     }
 
     let mut hash = hash;
-    for proof in proofs.into_iter() {
-        matches.push(proof);
-        hash = sort_hash_of(hash, proof);
-        if matches.contains(&hash) {
+    for proof in proofs.iter() {
+        matches.insert(*proof);
+        hash = sort_hash_of(hasher, hash, *proof);
+        if !matches.insert(hash) {
             return true;
         }
-        matches.push(hash)
     }
 
     false
 }
 
-// pre_matches takes 3 static proofs and calculate document root.
-// the calculated document root is then compared with the document root that is passed.
-// if the calculated document root matches, returns true and array of precomputed hashes
+// computes the root of the multiproof, see [MultiProof] for how nodes are paired.
+// returns None if the multiproof does not have exactly the hashes and flags its leaves require.
+fn multi_proof_root(multi_proof: &MultiProof, hasher: HashFunction) -> Option<H256> {
+    let MultiProof {
+        leaves,
+        hashes,
+        flags,
+    } = multi_proof;
+    // every pair merges two nodes into one, until a single one is left
+    if leaves.len() + hashes.len() != flags.len() + 1 {
+        return None;
+    }
+
+    let mut computed: Vec<H256> = Vec::with_capacity(flags.len());
+    let (mut leaf_pos, mut computed_pos, mut hash_pos) = (0, 0, 0);
+    for flag in flags.iter() {
+        let mut next_node = |computed: &Vec<H256>| {
+            if leaf_pos < leaves.len() {
+                leaf_pos += 1;
+                Some(leaves[leaf_pos - 1])
+            } else {
+                computed_pos += 1;
+                computed.get(computed_pos - 1).cloned()
+            }
+        };
+
+        let a = next_node(&computed)?;
+        let b = if *flag {
+            next_node(&computed)?
+        } else {
+            hash_pos += 1;
+            *hashes.get(hash_pos - 1)?
+        };
+        computed.push(sort_hash_of(hasher, a, b));
+    }
+
+    if hash_pos != hashes.len() {
+        return None;
+    }
+
+    match computed.last() {
+        Some(root) => Some(*root),
+        None => leaves.first().cloned(),
+    }
+}
+
+// pre_matches takes the static proofs and calculate document root as described by the layout.
+// returns the calculated document root, to be compared with the anchored one, and the set of
+// precomputed hashes.
 // precomputed hashes are used while validating the proofs.
-//
-//
-// Computing Document Root:
-//                      DocumentRoot
-//                      /          \
-//          Signing Root            Signature Root
-//          /          \
-//   data root 1     data root 2
-fn pre_matches(static_proofs: [H256; 3], doc_root: H256) -> (bool, Vec<H256>) {
-    let mut matches = Vec::new();
-    let basic_data_root = static_proofs[0];
-    let zk_data_root = static_proofs[1];
-    let signature_root = static_proofs[2];
-    matches.push(basic_data_root);
-    matches.push(zk_data_root);
-    let signing_root = hash_of(basic_data_root, zk_data_root);
-    matches.push(signing_root);
-    matches.push(signature_root);
-    let calc_doc_root = hash_of(signing_root, signature_root);
-    matches.push(calc_doc_root);
-    (calc_doc_root == doc_root, matches)
+// returns None if the static proofs do not fit the layout.
+fn pre_matches(layout: &DocumentLayout, static_proofs: &[H256]) -> Option<(H256, BTreeSet<H256>)> {
+    if static_proofs.len() != layout.static_roots as usize {
+        return None;
+    }
+
+    // the combinations refer to the nodes by index, in the order they are computed
+    let mut nodes = static_proofs.to_vec();
+    for (a, b) in layout.combinations.iter() {
+        let a = *nodes.get(*a as usize)?;
+        let b = *nodes.get(*b as usize)?;
+        nodes.push(layout_hash_of(layout, a, b));
+    }
+
+    let calc_doc_root = *nodes.last()?;
+    Some((calc_doc_root, nodes.into_iter().collect()))
 }
 
 // appends deposit_address and all the hashes from the proofs and returns keccak hash of the result.
@@ -134,17 +516,223 @@ pub fn bundled_hash(proofs: Vec<Proof>, deposit_address: [u8; 20]) -> H256 {
     sp_io::hashing::keccak_256(hash.as_slice()).into()
 }
 
+/// Merkle tree of sorted hashes, used to generate proofs in tests and benchmarks.
+///
+/// The tree is stored as an array, the children of the node `i` being the nodes `2i + 1` and
+/// `2i + 2`. The leaves are the last nodes of the array, in reverse order, as in the OpenZeppelin
+/// merkle trees.
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub struct MerkleTree {
+    nodes: Vec<H256>,
+    hasher: HashFunction,
+}
+
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+impl MerkleTree {
+    /// Builds the tree of the given leaves. There must be at least one leaf.
+    pub fn new(leaves: Vec<H256>, hasher: HashFunction) -> Self {
+        let count = 2 * leaves.len() - 1;
+        let mut nodes = sp_std::vec![H256::zero(); count];
+        for (i, leaf) in leaves.into_iter().enumerate() {
+            nodes[count - 1 - i] = leaf;
+        }
+        for i in (0..count / 2).rev() {
+            nodes[i] = sort_hash_of(hasher, nodes[2 * i + 1], nodes[2 * i + 2]);
+        }
+
+        MerkleTree { nodes, hasher }
+    }
+
+    pub fn root(&self) -> H256 {
+        self.nodes[0]
+    }
+
+    pub fn hasher(&self) -> HashFunction {
+        self.hasher
+    }
+
+    fn node_index(&self, leaf: usize) -> usize {
+        self.nodes.len() - 1 - leaf
+    }
+
+    fn sibling(node: usize) -> usize {
+        if node % 2 == 1 {
+            node + 1
+        } else {
+            node - 1
+        }
+    }
+
+    /// Returns the sibling hashes from the given leaf up to the root.
+    pub fn proof_hashes(&self, leaf: usize) -> Vec<H256> {
+        let mut node = self.node_index(leaf);
+        let mut hashes = Vec::new();
+        while node > 0 {
+            hashes.push(self.nodes[Self::sibling(node)]);
+            node = (node - 1) / 2;
+        }
+        hashes
+    }
+
+    /// Returns the proof of the given leaf.
+    pub fn proof(&self, leaf: usize) -> Proof {
+        Proof::new(self.nodes[self.node_index(leaf)], self.proof_hashes(leaf))
+    }
+
+    /// Returns a multiproof of the given leaves.
+    pub fn multi_proof(&self, leaves: &[usize]) -> MultiProof {
+        let mut indexes: Vec<usize> = leaves.iter().map(|leaf| self.node_index(*leaf)).collect();
+        indexes.sort_by(|a, b| b.cmp(a));
+        indexes.dedup();
+
+        let mut queue: sp_std::collections::vec_deque::VecDeque<usize> =
+            indexes.iter().cloned().collect();
+        let mut hashes = Vec::new();
+        let mut flags = Vec::new();
+        while let Some(node) = queue.pop_front() {
+            if node == 0 {
+                break;
+            }
+            let sibling = Self::sibling(node);
+            if queue.front() == Some(&sibling) {
+                flags.push(true);
+                queue.pop_front();
+            } else {
+                flags.push(false);
+                hashes.push(self.nodes[sibling]);
+            }
+            queue.push_back((node - 1) / 2);
+        }
+
+        MultiProof {
+            leaves: indexes.into_iter().map(|node| self.nodes[node]).collect(),
+            hashes,
+            flags,
+        }
+    }
+}
+
+/// Perfect merkle tree of sorted hashes of `2^depth` leaves, of which only a few are given. The
+/// other leaves, and the nodes that only cover such leaves, are filler hashes that are never
+/// computed, so that proofs of deep trees can be generated in benchmarks. The leaves are ordered
+/// as in [MerkleTree], so that proofs of both trees have the same shape.
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub struct SparseMerkleTree {
+    // computed nodes of each level by position, from the leaves up to the root
+    levels: Vec<sp_std::collections::btree_map::BTreeMap<u64, H256>>,
+    hasher: HashFunction,
+}
+
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+impl SparseMerkleTree {
+    /// Builds the tree of the given depth from the given leaves and their positions.
+    pub fn new(depth: u32, leaves: Vec<(u64, H256)>, hasher: HashFunction) -> Self {
+        let mut tree = SparseMerkleTree {
+            levels: sp_std::vec![leaves.into_iter().collect()],
+            hasher,
+        };
+        for level in 0..depth as usize {
+            let parents = tree.levels[level]
+                .keys()
+                .map(|pos| pos / 2)
+                .collect::<BTreeSet<u64>>()
+                .into_iter()
+                .map(|pos| {
+                    let hash = sort_hash_of(
+                        hasher,
+                        tree.node(level, 2 * pos),
+                        tree.node(level, 2 * pos + 1),
+                    );
+                    (pos, hash)
+                })
+                .collect();
+            tree.levels.push(parents);
+        }
+        tree
+    }
+
+    pub fn root(&self) -> H256 {
+        self.node(self.levels.len() - 1, 0)
+    }
+
+    pub fn hasher(&self) -> HashFunction {
+        self.hasher
+    }
+
+    fn node(&self, level: usize, pos: u64) -> H256 {
+        match self.levels[level].get(&pos) {
+            Some(node) => *node,
+            None => self.hasher.hash(&(level as u32, pos).encode()),
+        }
+    }
+
+    /// Returns the sibling hashes from the leaf at the given position up to the root.
+    pub fn proof_hashes(&self, leaf: u64) -> Vec<H256> {
+        (0..self.levels.len() - 1)
+            .map(|level| self.node(level, (leaf >> level) ^ 1))
+            .collect()
+    }
+
+    /// Returns the proof of the leaf at the given position.
+    pub fn proof(&self, leaf: u64) -> Proof {
+        Proof::new(self.node(0, leaf), self.proof_hashes(leaf))
+    }
+
+    /// Returns a multiproof of the leaves at the given positions.
+    pub fn multi_proof(&self, leaves: &[u64]) -> MultiProof {
+        let mut positions = leaves.to_vec();
+        positions.sort();
+        positions.dedup();
+
+        let depth = self.levels.len() - 1;
+        let mut queue: sp_std::collections::vec_deque::VecDeque<(usize, u64)> =
+            positions.iter().map(|pos| (0, *pos)).collect();
+        let mut hashes = Vec::new();
+        let mut flags = Vec::new();
+        while let Some((level, pos)) = queue.pop_front() {
+            if level == depth {
+                break;
+            }
+            if queue.front() == Some(&(level, pos ^ 1)) {
+                flags.push(true);
+                queue.pop_front();
+            } else {
+                flags.push(false);
+                hashes.push(self.node(level, pos ^ 1));
+            }
+            queue.push_back((level + 1, pos / 2));
+        }
+
+        MultiProof {
+            leaves: positions.into_iter().map(|pos| self.node(0, pos)).collect(),
+            hashes,
+            flags,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::proofs::{
-        bundled_hash, pre_matches, sort_hash_of, validate_proof, validate_proofs, Proof,
+        bundled_hash, check_multi_proof_limits, check_proofs_limits, pre_matches, sort_hash_of,
+        validate_each_proof, validate_multi_proof, validate_proof, validate_proofs, DocumentLayout,
+        HashFunction, MerkleTree, MultiProof, Proof, ProofsError, SparseMerkleTree,
+        DOCUMENT_LAYOUT_V1,
     };
+    use codec::Encode;
+    use hex_literal::hex;
     use sp_core::H256;
+    use sp_std::collections::btree_set::BTreeSet;
+
+    fn layout_v1() -> DocumentLayout {
+        DocumentLayout::from_version(DOCUMENT_LAYOUT_V1).unwrap()
+    }
 
     fn proof_from_hash(a: H256) -> Proof {
         Proof {
             leaf_hash: a,
             sorted_hashes: Vec::new(),
+            hasher: None,
         }
     }
 
@@ -165,7 +753,7 @@ mod tests {
             235, 120, 71, 98, 105, 91, 212, 180, 209, 164, 91, 87, 156,
         ]
         .into();
-        let got = sort_hash_of(a, b);
+        let got = sort_hash_of(HashFunction::Blake2_256, a, b);
         assert!(res == got, "{:?} {:?}", res, got)
     }
 
@@ -186,7 +774,7 @@ mod tests {
             158, 210, 122, 74, 55, 7, 101, 217, 146, 206, 194, 114, 79, 169,
         ]
         .into();
-        let got = sort_hash_of(a, b);
+        let got = sort_hash_of(HashFunction::Blake2_256, a, b);
         assert!(res == got, "{:?} {:?}", res, got)
     }
 
@@ -265,6 +853,7 @@ mod tests {
                 ]
                 .into(),
             ],
+            hasher: None,
         };
 
         let doc_root: H256 = [
@@ -313,6 +902,7 @@ mod tests {
                 ]
                 .into(),
             ],
+            hasher: None,
         };
 
         let doc_root: H256 = [
@@ -327,23 +917,26 @@ mod tests {
     #[test]
     fn validate_proof_success() {
         let (proof, root, static_proofs) = get_valid_proof();
-        let (_, mut matches) = pre_matches(static_proofs, root);
+        let (calc_root, mut matches) = pre_matches(&layout_v1(), &static_proofs).unwrap();
+        assert_eq!(calc_root, root);
         assert!(validate_proof(
             &mut matches,
             proof.leaf_hash,
-            proof.sorted_hashes
+            &proof.sorted_hashes,
+            HashFunction::Blake2_256
         ))
     }
 
     #[test]
     fn validate_proof_failed() {
         let (proof, doc_root) = get_invalid_proof();
-        let mut matches = vec![doc_root];
+        let mut matches: BTreeSet<H256> = vec![doc_root].into_iter().collect();
 
         assert!(!validate_proof(
             &mut matches,
             proof.leaf_hash,
-            proof.sorted_hashes
+            &proof.sorted_hashes,
+            HashFunction::Blake2_256
         ))
     }
 
@@ -356,18 +949,21 @@ mod tests {
             ]
             .into(),
             sorted_hashes: vec![],
+            hasher: None,
         };
 
-        let mut matches: Vec<H256> = vec![[
+        let mut matches: BTreeSet<H256> = vec![H256::from([
             25, 102, 189, 46, 86, 242, 48, 217, 254, 16, 20, 211, 98, 206, 125, 92, 167, 175, 70,
             161, 35, 135, 33, 80, 225, 247, 4, 240, 138, 86, 167, 142,
-        ]
-        .into()];
+        ])]
+        .into_iter()
+        .collect();
 
         assert!(!validate_proof(
             &mut matches,
             proof.leaf_hash,
-            proof.sorted_hashes
+            &proof.sorted_hashes,
+            HashFunction::Blake2_256
         ))
     }
 
@@ -376,7 +972,16 @@ mod tests {
         let (vp1, doc_root, static_proofs) = get_valid_proof();
         let (vp2, _, _) = get_valid_proof();
         let proofs = vec![vp1, vp2];
-        assert!(validate_proofs(doc_root, &proofs, static_proofs))
+        assert_eq!(
+            validate_proofs(
+                doc_root,
+                &proofs,
+                &layout_v1(),
+                &static_proofs,
+                HashFunction::Blake2_256
+            ),
+            Ok(())
+        )
     }
 
     #[test]
@@ -384,13 +989,510 @@ mod tests {
         let (vp, doc_root, static_proofs) = get_valid_proof();
         let (ivp, _) = get_invalid_proof();
         let proofs = vec![vp, ivp];
-        assert!(!validate_proofs(doc_root, &proofs, static_proofs))
+        assert_eq!(
+            validate_proofs(
+                doc_root,
+                &proofs,
+                &layout_v1(),
+                &static_proofs,
+                HashFunction::Blake2_256
+            ),
+            Err(ProofsError::InvalidProof(1))
+        )
+    }
+
+    #[test]
+    fn validate_proofs_doc_root_mismatch() {
+        let (vp, _, static_proofs) = get_valid_proof();
+        let proofs = vec![vp];
+        assert_eq!(
+            validate_proofs(
+                H256::zero(),
+                &proofs,
+                &layout_v1(),
+                &static_proofs,
+                HashFunction::Blake2_256
+            ),
+            Err(ProofsError::DocumentRootMismatch)
+        )
     }
 
     #[test]
     fn validate_proofs_no_proofs() {
         let (_, doc_root, static_proofs) = get_valid_proof();
         let proofs = vec![];
-        assert!(!validate_proofs(doc_root, &proofs, static_proofs))
+        assert_eq!(
+            validate_proofs(
+                doc_root,
+                &proofs,
+                &layout_v1(),
+                &static_proofs,
+                HashFunction::Blake2_256
+            ),
+            Err(ProofsError::EmptyProofs)
+        )
+    }
+
+    #[test]
+    fn validate_each_proof_success() {
+        let (vp1, doc_root, static_proofs) = get_valid_proof();
+        let (vp2, _, _) = get_valid_proof();
+        let validation = validate_each_proof(
+            doc_root,
+            &vec![vp1, vp2],
+            &layout_v1(),
+            &static_proofs,
+            HashFunction::Blake2_256,
+        );
+        assert_eq!(validation.doc_root, doc_root);
+        assert_eq!(validation.valid_proofs, vec![true, true]);
+        assert_eq!(validation.error, None);
+    }
+
+    #[test]
+    fn validate_each_proof_failed() {
+        let (vp, doc_root, static_proofs) = get_valid_proof();
+        let (ivp, _) = get_invalid_proof();
+        let validation = validate_each_proof(
+            doc_root,
+            &vec![ivp, vp],
+            &layout_v1(),
+            &static_proofs,
+            HashFunction::Blake2_256,
+        );
+        assert_eq!(validation.doc_root, doc_root);
+        assert_eq!(validation.valid_proofs, vec![false, true]);
+        assert_eq!(validation.error, Some(ProofsError::InvalidProof(0)));
+    }
+
+    #[test]
+    fn validate_each_proof_does_not_cache_invalid_proof() {
+        let (_, doc_root, static_proofs) = get_valid_proof();
+        let (ivp, _) = get_invalid_proof();
+        // a proof of one of the sibling hashes of the invalid proof
+        let reusing = proof_from_hash(ivp.sorted_hashes[0]);
+        let validation = validate_each_proof(
+            doc_root,
+            &vec![ivp, reusing],
+            &layout_v1(),
+            &static_proofs,
+            HashFunction::Blake2_256,
+        );
+        assert_eq!(validation.valid_proofs, vec![false, false]);
+        assert_eq!(validation.error, Some(ProofsError::InvalidProof(0)));
+    }
+
+    #[test]
+    fn validate_each_proof_doc_root_mismatch() {
+        let (vp, doc_root, static_proofs) = get_valid_proof();
+        let validation = validate_each_proof(
+            H256::zero(),
+            &vec![vp],
+            &layout_v1(),
+            &static_proofs,
+            HashFunction::Blake2_256,
+        );
+        assert_eq!(validation.doc_root, doc_root);
+        assert_eq!(validation.valid_proofs, vec![true]);
+        assert_eq!(validation.error, Some(ProofsError::DocumentRootMismatch));
+    }
+
+    #[test]
+    fn validate_each_proof_no_proofs() {
+        let (_, doc_root, static_proofs) = get_valid_proof();
+        let validation = validate_each_proof(
+            doc_root,
+            &vec![],
+            &layout_v1(),
+            &static_proofs,
+            HashFunction::Blake2_256,
+        );
+        assert!(validation.valid_proofs.is_empty());
+        assert_eq!(validation.error, Some(ProofsError::EmptyProofs));
+    }
+
+    #[test]
+    fn validate_proofs_static_proofs_mismatch() {
+        let (vp, doc_root, static_proofs) = get_valid_proof();
+        let proofs = vec![vp];
+        assert_eq!(
+            validate_proofs(
+                doc_root,
+                &proofs,
+                &layout_v1(),
+                &static_proofs[..2],
+                HashFunction::Blake2_256
+            ),
+            Err(ProofsError::StaticProofsMismatch)
+        );
+
+        let validation = validate_each_proof(
+            doc_root,
+            &proofs,
+            &layout_v1(),
+            &static_proofs[..2],
+            HashFunction::Blake2_256,
+        );
+        assert_eq!(validation.error, Some(ProofsError::StaticProofsMismatch));
+    }
+
+    #[test]
+    fn document_layout_unknown_version() {
+        assert_eq!(DocumentLayout::from_version(0), None);
+        assert_eq!(DocumentLayout::from_version(DOCUMENT_LAYOUT_V1 + 1), None);
+    }
+
+    #[test]
+    fn pre_matches_with_custom_layout() {
+        // four static roots combined as a balanced tree of sorted hashes
+        let layout = DocumentLayout {
+            static_roots: 4,
+            combinations: &[(0, 1), (2, 3), (4, 5)],
+            sorted: true,
+            hasher: HashFunction::Blake2_256,
+        };
+        let static_proofs: Vec<H256> = (1..=4u8).map(|i| H256::repeat_byte(i)).collect();
+        let doc_root = sort_hash_of(
+            HashFunction::Blake2_256,
+            sort_hash_of(HashFunction::Blake2_256, static_proofs[1], static_proofs[0]),
+            sort_hash_of(HashFunction::Blake2_256, static_proofs[3], static_proofs[2]),
+        );
+
+        let (calc_doc_root, matches) = pre_matches(&layout, &static_proofs).unwrap();
+        assert_eq!(calc_doc_root, doc_root);
+        assert_eq!(matches.len(), 7);
+        assert!(matches.contains(&doc_root));
+
+        let proofs = vec![proof_from_hash(static_proofs[2])];
+        assert_eq!(
+            validate_proofs(
+                doc_root,
+                &proofs,
+                &layout,
+                &static_proofs,
+                HashFunction::Blake2_256
+            ),
+            Ok(())
+        );
+        assert_eq!(pre_matches(&layout, &static_proofs[..3]), None);
+    }
+
+    #[test]
+    fn hash_function_test_vectors() {
+        // reference digests of "abc"
+        assert_eq!(
+            HashFunction::Blake2_256.hash(b"abc"),
+            H256::from(hex!(
+                "bddd813c634239723171ef3fee98579b94964e3bb1cb3e427262c8c068d52319"
+            ))
+        );
+        assert_eq!(
+            HashFunction::Keccak256.hash(b"abc"),
+            H256::from(hex!(
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+            ))
+        );
+        assert_eq!(
+            HashFunction::Sha2_256.hash(b"abc"),
+            H256::from(hex!(
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+            ))
+        );
+    }
+
+    #[test]
+    fn sort_hash_of_with_each_hash_function() {
+        // same nodes as in hash_of_a_lt_b
+        let a = H256::from(hex!(
+            "55bf74f5378b1d938bb7a13f3c6582691ed7a2df85e93ab56fa118bac9a21244"
+        ));
+        let b = H256::from(hex!(
+            "7e475d85728121e0b1c3dadb2590f8a69aea6fc539d174e85abdad7a83be8f8e"
+        ));
+        let cases = vec![
+            (
+                HashFunction::Blake2_256,
+                hex!("1d6a6735555e97986121c74dc7e5da6ffb098aeb784762695bd4b4d1a45b579c"),
+            ),
+            (
+                HashFunction::Keccak256,
+                hex!("6e0f09e8b585dfaa5f3299bda833487d8b946faf3361277618b30a3074721144"),
+            ),
+            (
+                HashFunction::Sha2_256,
+                hex!("c221cf2c64c121bc295a491915ef1c46d1f67bcc575f490ba3b48ea862d6478f"),
+            ),
+        ];
+
+        for (hasher, expected) in cases {
+            assert_eq!(sort_hash_of(hasher, a, b), H256::from(expected));
+            assert_eq!(sort_hash_of(hasher, b, a), H256::from(expected));
+        }
+    }
+
+    #[test]
+    fn validate_proofs_with_proof_hasher() {
+        let layout = DocumentLayout {
+            static_roots: 1,
+            combinations: &[],
+            sorted: false,
+            hasher: HashFunction::Blake2_256,
+        };
+        let leaf = H256::repeat_byte(1);
+        let sibling = H256::repeat_byte(2);
+        let root = sort_hash_of(HashFunction::Keccak256, leaf, sibling);
+        let proof = Proof::new(leaf, vec![sibling]);
+
+        assert_eq!(
+            validate_proofs(
+                root,
+                &vec![proof.clone()],
+                &layout,
+                &[root],
+                HashFunction::Keccak256
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            validate_proofs(
+                root,
+                &vec![proof.clone()],
+                &layout,
+                &[root],
+                HashFunction::Blake2_256
+            ),
+            Err(ProofsError::InvalidProof(0))
+        );
+
+        // the hash function of the proof takes precedence
+        let proof = proof.with_hasher(Some(HashFunction::Keccak256));
+        assert_eq!(
+            validate_proofs(
+                root,
+                &vec![proof],
+                &layout,
+                &[root],
+                HashFunction::Blake2_256
+            ),
+            Ok(())
+        );
+    }
+
+    fn single_root_layout() -> DocumentLayout {
+        DocumentLayout {
+            static_roots: 1,
+            combinations: &[],
+            sorted: false,
+            hasher: HashFunction::Blake2_256,
+        }
+    }
+
+    fn merkle_tree(leaves: u8) -> MerkleTree {
+        MerkleTree::new(
+            (0..leaves).map(H256::repeat_byte).collect(),
+            HashFunction::Blake2_256,
+        )
+    }
+
+    #[test]
+    fn validate_multi_proof_success() {
+        let layout = single_root_layout();
+        for leaves in 1..=9 {
+            let tree = merkle_tree(leaves);
+            let root = tree.root();
+            let all: Vec<usize> = (0..leaves as usize).collect();
+            for proven in vec![
+                vec![0],
+                vec![leaves as usize - 1],
+                all.clone(),
+                all[1..].to_vec(),
+            ] {
+                if proven.is_empty() {
+                    continue;
+                }
+                let multi_proof = tree.multi_proof(&proven);
+                assert_eq!(
+                    validate_multi_proof(root, &multi_proof, &layout, &[root], tree.hasher()),
+                    Ok(()),
+                    "{} leaves, proving {:?}",
+                    leaves,
+                    proven
+                );
+            }
+
+            // proofs of the same tree are accepted by validate_proofs too
+            let proofs = all.iter().map(|leaf| tree.proof(*leaf)).collect();
+            assert_eq!(
+                validate_proofs(root, &proofs, &layout, &[root], tree.hasher()),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn sparse_merkle_tree_proofs() {
+        let layout = single_root_layout();
+
+        // a sparse tree of all leaves is the same as a merkle tree of them
+        let tree = merkle_tree(8);
+        let sparse = SparseMerkleTree::new(
+            3,
+            (0..8).map(|i| (i, H256::repeat_byte(i as u8))).collect(),
+            HashFunction::Blake2_256,
+        );
+        assert_eq!(sparse.root(), tree.root());
+        assert_eq!(sparse.multi_proof(&[1, 2, 6]), tree.multi_proof(&[1, 2, 6]));
+
+        for depth in vec![0, 1, 6, 32] {
+            let mut proven: Vec<u64> = (0..4).map(|i| (i << depth) / 4).collect();
+            proven.dedup();
+            let sparse = SparseMerkleTree::new(
+                depth,
+                proven
+                    .iter()
+                    .enumerate()
+                    .map(|(i, pos)| (*pos, H256::repeat_byte(i as u8 + 1)))
+                    .collect(),
+                HashFunction::Blake2_256,
+            );
+            let root = sparse.root();
+
+            let proofs = proven.iter().map(|pos| sparse.proof(*pos)).collect();
+            assert_eq!(
+                validate_proofs(root, &proofs, &layout, &[root], sparse.hasher()),
+                Ok(()),
+                "depth {}",
+                depth
+            );
+            assert_eq!(
+                validate_multi_proof(
+                    root,
+                    &sparse.multi_proof(&proven),
+                    &layout,
+                    &[root],
+                    sparse.hasher()
+                ),
+                Ok(()),
+                "depth {}",
+                depth
+            );
+        }
+    }
+
+    #[test]
+    fn validate_multi_proof_failed() {
+        let layout = single_root_layout();
+        let tree = merkle_tree(8);
+        let root = tree.root();
+        let multi_proof = tree.multi_proof(&[1, 2, 6]);
+
+        let empty = MultiProof::default();
+        assert_eq!(
+            validate_multi_proof(root, &empty, &layout, &[root], tree.hasher()),
+            Err(ProofsError::EmptyProofs)
+        );
+
+        let mut malformed = multi_proof.clone();
+        malformed.flags.pop();
+        assert_eq!(
+            validate_multi_proof(root, &malformed, &layout, &[root], tree.hasher()),
+            Err(ProofsError::MalformedMultiProof)
+        );
+
+        let mut malformed = multi_proof.clone();
+        malformed.hashes.push(H256::zero());
+        malformed.flags.push(false);
+        assert_eq!(
+            validate_multi_proof(root, &malformed, &layout, &[root], tree.hasher()),
+            Err(ProofsError::InvalidMultiProof)
+        );
+
+        let mut invalid = multi_proof.clone();
+        invalid.leaves[0] = H256::zero();
+        assert_eq!(
+            validate_multi_proof(root, &invalid, &layout, &[root], tree.hasher()),
+            Err(ProofsError::InvalidMultiProof)
+        );
+
+        assert_eq!(
+            validate_multi_proof(root, &multi_proof, &layout, &[root], HashFunction::Sha2_256),
+            Err(ProofsError::InvalidMultiProof)
+        );
+        assert_eq!(
+            validate_multi_proof(H256::zero(), &multi_proof, &layout, &[root], tree.hasher()),
+            Err(ProofsError::DocumentRootMismatch)
+        );
+    }
+
+    #[test]
+    fn validate_proof_caches_each_node_once() {
+        let tree = merkle_tree(16);
+        let (_, mut matches) = pre_matches(&single_root_layout(), &[tree.root()]).unwrap();
+        for leaf in 0..16 {
+            let proof = tree.proof(leaf);
+            assert!(validate_proof(
+                &mut matches,
+                proof.leaf_hash,
+                &proof.sorted_hashes,
+                tree.hasher()
+            ));
+        }
+
+        // each node is cached once however many proofs share it, and the first leaf of each
+        // pair is never cached itself, only its sibling is
+        assert_eq!(matches.len(), 2 * 16 - 1 - 8);
+
+        // a proof of an already cached leaf stops at the leaf
+        let proof = Proof::new(tree.proof(3).leaf_hash, vec![H256::zero()]);
+        assert!(validate_proof(
+            &mut matches,
+            proof.leaf_hash,
+            &proof.sorted_hashes,
+            tree.hasher()
+        ));
+    }
+
+    #[test]
+    fn proofs_limits() {
+        let tree = merkle_tree(16);
+        let proofs: Vec<Proof> = (0..4).map(|leaf| tree.proof(leaf)).collect();
+
+        // every proof of a tree of 16 leaves has 4 hashes
+        assert_eq!(check_proofs_limits(&proofs, 4, 4), Ok(()));
+        assert_eq!(
+            check_proofs_limits(&proofs, 3, 4),
+            Err(ProofsError::TooManyProofs)
+        );
+        assert_eq!(
+            check_proofs_limits(&proofs, 4, 3),
+            Err(ProofsError::ProofTooLong(0))
+        );
+
+        let multi_proof = tree.multi_proof(&[0, 1, 2, 3]);
+        assert_eq!(check_multi_proof_limits(&multi_proof, 4, 4), Ok(()));
+        assert_eq!(
+            check_multi_proof_limits(&multi_proof, 3, 4),
+            Err(ProofsError::TooManyProofs)
+        );
+
+        let mut long = multi_proof.clone();
+        long.hashes = vec![H256::zero(); 17];
+        assert_eq!(
+            check_multi_proof_limits(&long, 4, 4),
+            Err(ProofsError::MultiProofTooLong)
+        );
+    }
+
+    #[test]
+    fn multi_proof_encoded_size() {
+        let tree = merkle_tree(16);
+        let proven: Vec<usize> = (0..16).step_by(2).collect();
+        let proofs: Vec<Proof> = proven.iter().map(|leaf| tree.proof(*leaf)).collect();
+        let multi_proof = tree.multi_proof(&proven);
+
+        // every other leaf of 16: each proof carries 4 siblings, the multiproof only
+        // carries the 8 unproven leaves
+        assert_eq!(multi_proof.hashes.len(), 8);
+        assert!(multi_proof.encode().len() < proofs.encode().len() / 2);
     }
 }
@@ -1,18 +1,20 @@
-//#[cfg(feature = "runtime-benchmarks")]
-use crate::nft;
-//use crate::registry::{Error, mock::*};
-use crate::registry::{Error, types::*};
-use crate::registry::{Module, Call, Trait};
-use sp_std::{vec, prelude::*};
-use crate::proofs;
-use sp_core::{H256, Encode};
-use sp_io::hashing::blake2_128;
-use frame_benchmarking::{benchmarks, account};
+//! Benchmarks for the verifiable attributes registry module.
+
+use super::*;
+use codec::Encode;
+use frame_benchmarking::{account, benchmarks};
+use frame_support::traits::Currency;
 use frame_system::RawOrigin;
-use sp_runtime::{
-    //testing::Header,
-    traits::{BadOrigin, BlakeTwo256, Hash, IdentityLookup, Block as BlockT},
-};
+use sp_runtime::traits::Bounded;
+use sp_std::vec;
+
+const SEED: u32 = 0;
+
+/// Minimum depth of the document trees of the benchmarked mints, enough to hold `MaxProofs`
+/// leaves. The maximum depth is `MaxProofHashes`, the longest proof a mint accepts.
+fn min_depth<T: Trait>() -> u32 {
+    32 - T::MaxProofs::get().saturating_sub(1).leading_zeros()
+}
 
 // Hash two hashes
 fn hash_of(a: H256, b: H256) -> H256 {
@@ -21,100 +23,162 @@ fn hash_of(a: H256, b: H256) -> H256 {
     h.extend_from_slice(&b[..]);
     sp_io::hashing::blake2_256(&h).into()
 }
-// Generate document root from static hashes
-fn doc_root(static_hashes: [H256; 3]) -> H256 {
-    let basic_data_root = static_hashes[0];
-    let zk_data_root    = static_hashes[1];
-    let signature_root  = static_hashes[2];
-    let signing_root    = hash_of(basic_data_root, zk_data_root);
-    hash_of(signing_root, signature_root)
-}
 
-// Some dummy proofs data useful for testing. Returns proofs, static hashes, and document root
-fn proofs_data() -> (Vec<Proof<H256>>, [H256; 3], H256) {
-    let proofs = vec![
-        Proof {
-            value: vec![1],
-            salt: vec![0],
-            property: b"AMOUNT".to_vec(),
+// Creates a registry with `p` fields and anchors a document proving all of them. The document
+// tree has `2^d` leaves, the proven ones being spread evenly among unproven ones so that they
+// share as few nodes as possible. Only the nodes above the proven leaves are computed, so that
+// trees as deep as `MaxProofHashes` can be set up. Returns the caller, the id of the token and
+// the mint info proving the document, one proof at a time or with a multiproof.
+fn setup_mint<T: Trait>(p: u32, d: u32, multi_proof: bool)
+    -> (T::AccountId, AssetId, MintInfo<T::Hash, H256>) {
+    let caller: T::AccountId = account("caller", 0, SEED);
+    <pallet_balances::Module<T> as Currency<_>>::make_free_balance_be(
+        &caller,
+        <T as pallet_balances::Trait>::Balance::max_value(),
+    );
+
+    // The last field is the token id, which is added by the registry
+    let registry_info = RegistryInfo {
+        owner_can_burn: false,
+        fields: (1..p).map(|i| (b"FIELD", i).encode()).collect(),
+        hasher: proofs::HashFunction::Blake2_256,
+    };
+    let registry_id = <Module<T> as VerifierRegistry>::create_registry(caller.clone(), registry_info)
+        .expect("registry is created");
+    let token_id = TokenId::one();
+    let mut token_enc = [0u8; 32];
+    token_id.to_big_endian(&mut token_enc);
+
+    let mut proofs: Vec<Proof<H256>> = (1..p)
+        .map(|i| Proof {
+            value: i.encode(),
+            property: (b"FIELD", i).encode(),
+            salt: [1; 32],
             hashes: vec![],
-        }];
-    let data_root    = proofs::Proof::from(proofs[0].clone()).leaf_hash;
-    let zk_data_root = <Test as frame_system::Trait>::Hashing::hash_of(&0);
-    let sig_root     = <Test as frame_system::Trait>::Hashing::hash_of(&0);
-    //let zk_data_root = sp_io::hashing::keccak_256(&[0]).into();
-    //let sig_root     = sp_io::hashing::keccak_256(&[0]).into();
-    let static_hashes = [data_root, zk_data_root, sig_root];
-    let doc_root     = doc_root(static_hashes);
-
-    (proofs, static_hashes, doc_root)
-}
+            hasher: None,
+        })
+        .collect();
+    proofs.push(Proof {
+        value: token_enc.to_vec(),
+        property: [NFTS_PREFIX, registry_id.as_bytes()].concat(),
+        salt: [1; 32],
+        hashes: vec![],
+        hasher: None,
+    });
 
-const SEED: u32 = 0;
+    // Proven leaves are every `2^d / p` leaves of the document tree
+    let leaf_hashes: Vec<H256> = proofs.iter()
+        .map(|proof| proofs::Proof::from(proof.clone()).leaf_hash)
+        .collect();
+    let step = (1u64 << d) / p as u64;
+    let proven: Vec<u64> = (0..p as u64).map(|i| i * step).collect();
+    let tree = proofs::SparseMerkleTree::new(
+        d,
+        proven.iter().cloned().zip(leaf_hashes.iter().cloned()).collect(),
+        proofs::HashFunction::Blake2_256,
+    );
+
+    let multi_proof = if multi_proof {
+        let multi_proof = tree.multi_proof(&proven);
+        Some(types::MultiProof {
+            leaves: multi_proof.leaves.iter()
+                .map(|leaf| leaf_hashes.iter().position(|l| l == leaf).unwrap_or_default() as u32)
+                .collect(),
+            hashes: multi_proof.hashes,
+            flags: multi_proof.flags,
+        })
+    } else {
+        for (proof, leaf) in proofs.iter_mut().zip(proven) {
+            proof.hashes = tree.proof_hashes(leaf);
+        }
+        None
+    };
+
+    // Anchor the document
+    let data_root = tree.root();
+    let zk_data_root: H256 = sp_io::hashing::blake2_256(&[0]).into();
+    let signature_root: H256 = sp_io::hashing::blake2_256(&[1]).into();
+    let doc_root = hash_of(hash_of(data_root, zk_data_root), signature_root);
+    let mut anchored_doc_root = T::Hash::default();
+    anchored_doc_root.as_mut().copy_from_slice(doc_root.as_bytes());
+
+    let pre_image = T::Hashing::hash_of(&0);
+    let anchor_id = pre_image.using_encoded(T::Hashing::hash);
+    let stored_until_date = <pallet_timestamp::Module<T>>::get()
+        + T::Moment::from((crate::common::MS_PER_DAY * 2) as u32);
+    <anchor::Module<T>>::commit(
+        RawOrigin::Signed(caller.clone()).into(),
+        pre_image,
+        anchored_doc_root,
+        T::Hashing::hash_of(&0),
+        stored_until_date,
+    ).expect("document is anchored");
+
+    let mint_info = MintInfo {
+        anchor_id,
+        proofs,
+        multi_proof,
+        document_layout: proofs::DOCUMENT_LAYOUT_V1,
+        static_hashes: vec![data_root, zk_data_root, signature_root],
+        evicted_anchor: None,
+    };
+
+    (caller, AssetId(registry_id, token_id), mint_info)
+}
 
 benchmarks! {
-    _ { /*let seed = 0 .. 10000;*/ }
+    _ { }
 
     mint {
-        //let owner     = 1;
-        let owner: T::AccountId = account("owner", 0, SEED);
-        let _owner = account("owner", 0, SEED);
-        let origin = RawOrigin::Signed(_owner);
-
-        // Anchor data
-        let pre_image = <T as frame_system::Trait>::Hashing::hash_of(&0);
-        let anchor_id = (pre_image).using_encoded(<T as frame_system::Trait>::Hashing::hash);
-
-        // Proofs data
-        let (proofs, static_hashes, doc_root) = proofs_data();
-
-        // Registry data
-        let registry_id = 0;
-        let nft_data: T::AssetInfo = AssetInfo {
-            registry_id,
-        };
-        let properties    =  proofs.iter().map(|p| p.property.clone()).collect();
-        let registry_info = RegistryInfo {
-            owner_can_burn: false,
-            fields: properties,
-        };
-
-        // Create registry
-        let _ = Module::<T>::create_registry(origin.clone(), registry_info)?;
-
-        // Place document anchor into storage for verification
-        let _ = <crate::anchor::Module<T>>::commit(
-            origin.clone(),
-            pre_image,
-            T::Hashing::hash_of(&doc_root.as_bytes()),
-            //H256::from_slice(doc_root.as_ref()),
-            // Proof does not matter here
-            <T as frame_system::Trait>::Hashing::hash_of(&0),
-            (100000 as u32).into())?;
-
-        let mint_info = MintInfo {
-            anchor_id: anchor_id,
-            proofs: proofs,
-            static_hashes: static_hashes,
-        };
-    }: mint(origin.clone(),
-            owner,
-            nft_data,
-            mint_info)
+        let p in 1 .. T::MaxProofs::get();
+        let d in min_depth::<T>() .. T::MaxProofHashes::get();
+        let (caller, asset_id, mint_info) = setup_mint::<T>(p, d, false);
+        let (registry_id, token_id) = asset_id.clone().destruct();
+    }: mint(RawOrigin::Signed(caller.clone()), caller.clone(), registry_id, token_id, Default::default(), mint_info)
+    verify {
+        assert_eq!(<nft::Module<T>>::account_for_asset(registry_id, token_id), Some(caller));
+    }
+
+    mint_multi_proof {
+        let p in 1 .. T::MaxProofs::get();
+        let d in min_depth::<T>() .. T::MaxProofHashes::get();
+        let (caller, asset_id, mint_info) = setup_mint::<T>(p, d, true);
+        let (registry_id, token_id) = asset_id.clone().destruct();
+    }: mint(RawOrigin::Signed(caller.clone()), caller.clone(), registry_id, token_id, Default::default(), mint_info)
+    verify {
+        assert_eq!(<nft::Module<T>>::account_for_asset(registry_id, token_id), Some(caller));
+    }
 }
 
-/*
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::registry::mock::{new_test_ext, Test};
-    use frame_support::assert_ok;
+    use crate::va_registry::mock::{new_test_ext, Test};
+
+    // Encoded size of the mint info of `p` proofs in a document tree of depth `d`
+    fn mint_info_size(p: u32, d: u32, multi_proof: bool) -> usize {
+        new_test_ext().execute_with(|| setup_mint::<Test>(p, d, multi_proof).2.encode().len())
+    }
 
     #[test]
-    fn test_benchmarks() {
-        new_test_ext().execute_with(|| {
-            assert_ok!(test_benchmark_mint::<Test>());
-        });
+    fn multi_proof_mint_info_size() {
+        for d in vec![6, 32] {
+            // a single proof carries the same hashes plus one flag per node, the position of
+            // the leaf and the length prefixes of the multiproof
+            assert_eq!(
+                mint_info_size(1, d, true),
+                mint_info_size(1, d, false) + d as usize + 7
+            );
+
+            // the nodes shared by several proofs are only given once
+            for p in vec![2, 8, 64] {
+                assert!(
+                    mint_info_size(p, d, true) < mint_info_size(p, d, false),
+                    "{} proofs of depth {}",
+                    p,
+                    d
+                );
+            }
+        }
     }
 }
-*/
@@ -1,6 +1,6 @@
 use sp_core::H256;
 use crate::va_registry::{Module, Trait};
-use crate::{nft, fees, va_registry};
+use crate::{anchor, nft, fees, va_registry};
 use frame_support::{impl_outer_origin, impl_outer_event, parameter_types, weights::Weight};
 use sp_runtime::{
     traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
@@ -14,6 +14,7 @@ impl_outer_event! {
     pub enum MetaEvent for Test {
         frame_system<T>,
         va_registry<T>,
+        anchor<T>,
         pallet_balances<T>,
         nft<T>,
         fees<T>,
@@ -64,7 +65,26 @@ impl nft::Trait for Test {
     type AssetInfo = crate::va_registry::types::AssetInfo;
 }
 
-impl crate::anchor::Trait for Test {}
+parameter_types! {
+    pub const PreCommitExpirationDurationBlocks: u64 = 800;
+    pub const PreCommitEvictionBucketMultiplier: u32 = 5;
+    pub const MaxLoopInTx: u64 = 500;
+    pub const StorageMaxDays: u32 = 376200;
+    pub const MaxEvictedAnchorProofNodes: u32 = 16;
+    pub const MaxEvictedAnchorProofSize: u32 = 16 * 1024;
+}
+
+impl crate::anchor::Trait for Test {
+    type Event = MetaEvent;
+    type WeightInfo = ();
+    type PreCommitExpirationDurationBlocks = PreCommitExpirationDurationBlocks;
+    type PreCommitEvictionBucketMultiplier = PreCommitEvictionBucketMultiplier;
+    type MaxLoopInTx = MaxLoopInTx;
+    type StorageMaxDays = StorageMaxDays;
+    type MaxEvictedAnchorProofNodes = MaxEvictedAnchorProofNodes;
+    type MaxEvictedAnchorProofSize = MaxEvictedAnchorProofSize;
+    type AdminOrigin = frame_system::EnsureRoot<Self::AccountId>;
+}
 
 impl pallet_timestamp::Trait for Test {
     type Moment = u64;
@@ -76,6 +96,11 @@ impl pallet_timestamp::Trait for Test {
 impl fees::Trait for Test {
     type Event = MetaEvent;
     type FeeChangeOrigin = frame_system::EnsureRoot<u64>;
+    type EvictionBountyPoolAccount = ();
+    type DefaultFeeDistribution = ();
+    type FeeKeyRegistrations = ();
+    type MinFeeChangeNotice = ();
+    type SponsoredFeeKeys = ();
 }
 
 parameter_types! {
@@ -97,8 +122,18 @@ impl pallet_authorship::Trait for Test {
     type EventHandler = ();
 }
 
+parameter_types! {
+    pub const MaxProofs: u32 = 4;
+    pub const MaxProofHashes: u32 = 4;
+    pub const MaxProofFieldLength: u32 = 64;
+}
+
 impl Trait for Test {
     type Event = MetaEvent;
+    type WeightInfo = ();
+    type MaxProofs = MaxProofs;
+    type MaxProofHashes = MaxProofHashes;
+    type MaxProofFieldLength = MaxProofFieldLength;
 }
 
 // System Under Test
@@ -19,9 +19,10 @@
 use frame_support::{
     ensure, dispatch, traits::Get,
     decl_module, decl_storage, decl_event, decl_error,
-    weights::{DispatchClass, Pays}};
-use sp_std::cmp::Eq;
-use sp_runtime::traits::Hash;
+    storage::IterableStorageMap,
+    weights::{DispatchClass, Pays, Weight}};
+use sp_std::{cmp::Eq, vec::Vec};
+use sp_runtime::{traits::Hash, SaturatedConversion};
 use sp_core::{H256, U256, H160};
 use frame_system::ensure_signed;
 use unique_assets::traits::Mintable;
@@ -41,8 +42,52 @@ pub mod tests;
 mod benchmarking;
 
 
+/// Version of the encoding of the registries in storage. Version 1 adds the hash function
+/// of the registries.
+pub const REGISTRIES_VERSION: u32 = 1;
+
+/// Weights of the extrinsics of this module, as generated from the benchmarks.
+pub trait WeightInfo {
+    /// Weight of a mint of `p` proofs of at most `d` hashes each, see the `mint` benchmark.
+    fn mint(p: u32, d: u32) -> Weight;
+    /// Weight of a mint of `p` proofs in a document tree of depth `d` proven by a multiproof,
+    /// see the `mint_multi_proof` benchmark.
+    fn mint_multi_proof(p: u32, d: u32) -> Weight;
+}
+
+/// Estimated weights of a mint, to be used until weights are generated from the benchmarks:
+/// a base weight, the hashing of the leaf of each proof and the hashing of the nodes of the
+/// proofs. Without a multiproof, each of the `d` nodes of each proof is hashed and looked up in
+/// the proven nodes. A multiproof hashes each node once, without lookups, which is at most
+/// `d` nodes per proof.
+impl WeightInfo for () {
+    fn mint(p: u32, d: u32) -> Weight {
+        (195_000_000 as Weight)
+            .saturating_add((2_000_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add((1_500_000 as Weight).saturating_mul((p as Weight).saturating_mul(d as Weight)))
+    }
+
+    fn mint_multi_proof(p: u32, d: u32) -> Weight {
+        (195_000_000 as Weight)
+            .saturating_add((2_000_000 as Weight).saturating_mul(p as Weight))
+            .saturating_add((1_000_000 as Weight).saturating_mul((p as Weight).saturating_mul(d as Weight)))
+    }
+}
+
 pub trait Trait: frame_system::Trait + nft::Trait + anchor::Trait {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+    /// Weights of the extrinsics of this module.
+    type WeightInfo: WeightInfo;
+
+    /// Maximum number of proofs of a mint.
+    type MaxProofs: Get<u32>;
+
+    /// Maximum number of hashes of each proof of a mint.
+    type MaxProofHashes: Get<u32>;
+
+    /// Maximum length in bytes of the value and of the property of each proof of a mint.
+    type MaxProofFieldLength: Get<u32>;
 }
 
 decl_storage! {
@@ -52,6 +97,8 @@ decl_storage! {
         /// A mapping of all created registries and their metadata.
         Registries: map hasher(blake2_128_concat) RegistryId => RegistryInfo;
         Owner get(fn owner_of): map hasher(blake2_128_concat) RegistryId => T::AccountId;
+        /// Version of the encoding of the registries, see [REGISTRIES_VERSION].
+        RegistriesVersion build(|_| REGISTRIES_VERSION): u32;
     }
 }
 
@@ -78,9 +125,31 @@ decl_error! {
         RegistryDoesNotExist,
         /// The registry id is too large.
         RegistryOverflow,
-        /// Unable to recreate the anchor hash from the proofs and data provided. This means
-        /// the [validate_proofs] method failed.
+        /// The token id or the properties of the proofs provided do not match the registry.
         InvalidProofs,
+        /// No proofs were provided to a mint call.
+        EmptyProofs,
+        /// The document layout version is not known.
+        UnknownDocumentLayout,
+        /// The number of static hashes does not match the document layout.
+        StaticProofsMismatch,
+        /// The document root computed from the static hashes does not match the anchored one.
+        DocumentRootMismatch,
+        /// One of the proofs provided does not lead to the document root.
+        InvalidProof,
+        /// The multiproof provided does not have the leaves, hashes and flags its proofs require.
+        MalformedMultiProof,
+        /// The multiproof provided does not lead to the document root.
+        InvalidMultiProof,
+        /// More proofs were provided than allowed by `MaxProofs`.
+        TooManyProofs,
+        /// One of the proofs provided has more hashes than allowed by `MaxProofHashes`.
+        ProofTooLong,
+        /// The value or the property of one of the proofs provided is longer than allowed by
+        /// `MaxProofFieldLength`.
+        ProofFieldTooLong,
+        /// The multiproof provided has more hashes or flags than its leaves may require.
+        MultiProofTooLong,
         /// The values vector provided to a mint call doesn't match the length of the specified
         /// registry's fields vector.
         InvalidMintingValues,
@@ -92,6 +161,10 @@ decl_module! {
         type Error = Error<T>;
         fn deposit_event() = default;
 
+        fn on_runtime_upgrade() -> Weight {
+            Self::migrate_registries()
+        }
+
         #[weight = T::DbWeight::get().reads_writes(1,2) + 195_000_000]
         pub fn create_registry(origin,
                                info: RegistryInfo,
@@ -107,9 +180,8 @@ decl_module! {
         }
 
         #[weight =
-            (mint_info.proofs.len().saturating_mul(1_000_000) as u64
-                + T::DbWeight::get().reads_writes(3,2)
-                + 195_000_000,
+            (<Module<T>>::mint_weight(mint_info)
+                .saturating_add(T::DbWeight::get().reads_writes(3,2)),
             DispatchClass::Normal,
             Pays::Yes)]
         pub fn mint(origin,
@@ -139,15 +211,158 @@ decl_module! {
 
 // Auxillary methods of the module for internal use
 impl<T: Trait> Module<T> {
-    fn get_document_root(anchor_id: T::Hash) -> Result<H256, dispatch::DispatchError> {
-        let root = match <anchor::Module<T>>::get_anchor_by_id(anchor_id) {
-            Some(anchor_data) => Ok(anchor_data.doc_root),
-            None => Err(Error::<T>::DocumentNotAnchored),
-        }?;
+    fn get_document_root(
+        anchor_id: T::Hash,
+        evicted_anchor: Option<anchor::EvictedAnchorProof>,
+    ) -> Result<H256, dispatch::DispatchError> {
+        let root = match evicted_anchor {
+            // An evicted anchor must be proven against the child trie root of its eviction day
+            Some(evicted) => <anchor::Module<T>>::get_evicted_anchor_by_id(evicted.day, anchor_id, evicted.proof)?
+                .doc_root,
+            None => match <anchor::Module<T>>::get_anchor_by_id(anchor_id)? {
+                Some(anchor_data) => Ok(anchor_data.doc_root),
+                None => Err(Error::<T>::DocumentNotAnchored),
+            }?,
+        };
 
         Ok( H256::from_slice(root.as_ref()) )
     }
 
+    /// Validates the proofs of a document anchored as `anchor_id` the same way as a mint in a
+    /// registry with the given hash function does, without the need for a transaction. Reports
+    /// the validity of each proof and why the proofs are not valid.
+    pub fn validate_document_proofs(
+        anchor_id: T::Hash,
+        document_layout: proofs::DocumentLayoutVersion,
+        hasher: proofs::HashFunction,
+        static_hashes: Vec<H256>,
+        proofs: Vec<Proof<H256>>,
+    ) -> proofs::ProofsValidation {
+        let doc_root = Self::get_document_root(anchor_id, None).ok();
+
+        if let Err(e) = Self::check_proofs_limits(&proofs, None) {
+            return proofs::ProofsValidation {
+                doc_root: Default::default(),
+                valid_proofs: proofs.iter().map(|_| false).collect(),
+                error: Some(e),
+            };
+        }
+
+        let layout = match proofs::DocumentLayout::from_version(document_layout) {
+            Some(layout) => layout,
+            None => return proofs::ProofsValidation {
+                doc_root: Default::default(),
+                valid_proofs: proofs.iter().map(|_| false).collect(),
+                error: Some(proofs::ProofsError::UnknownDocumentLayout),
+            },
+        };
+
+        // Generate leaf hashes, turn into proofs::Proof type for validation call
+        let proofs = proofs.into_iter()
+            .map(|p| p.into())
+            .collect();
+
+        let mut validation = proofs::validate_each_proof(doc_root.unwrap_or_default(),
+                                                         &proofs,
+                                                         &layout,
+                                                         &static_hashes,
+                                                         hasher);
+        if doc_root.is_none() {
+            validation.error = Some(proofs::ProofsError::AnchorNotFound);
+        }
+        validation
+    }
+
+    /// Computation weight of a mint, given by the number of proofs and the depth of the document
+    /// tree, which is the longest of the proofs. A multiproof is weighed by its number of flags
+    /// or hashes, whichever is greater, which is the number of nodes it hashes: the benchmarked
+    /// multiproofs of `p` leaves in a tree of depth `d` hash at least `p * (d - log2(p))` nodes,
+    /// so the depth is taken as the nodes per proof plus `log2(p)`. Nothing is capped, so that
+    /// oversized proofs are weighed by their actual size until `check_proofs_limits` rejects
+    /// them. The proof of an evicted anchor, if any, is weighed on top.
+    fn mint_weight(mint_info: &MintInfo<<T as frame_system::Trait>::Hash, H256>) -> Weight {
+        let evicted_anchor_weight = mint_info.evicted_anchor.as_ref()
+            .map_or(0, |evicted| <anchor::Module<T>>::evicted_anchor_proof_weight(&evicted.proof));
+        let proofs = mint_info.proofs.len() as u32;
+        let proofs_weight = match &mint_info.multi_proof {
+            Some(multi_proof) => {
+                let nodes = multi_proof.flags.len().max(multi_proof.hashes.len()) as u32;
+                let leaves = proofs.max(1);
+                let shared_depth = 32 - (leaves - 1).leading_zeros();
+                let depth = (nodes / leaves)
+                    .saturating_add((nodes % leaves != 0) as u32)
+                    .saturating_add(shared_depth);
+                T::WeightInfo::mint_multi_proof(proofs, depth)
+            },
+            None => {
+                let depth = mint_info.proofs.iter()
+                    .map(|proof| proof.hashes.len() as u32)
+                    .max()
+                    .unwrap_or_default();
+                T::WeightInfo::mint(proofs, depth)
+            },
+        };
+        proofs_weight.saturating_add(evicted_anchor_weight)
+    }
+
+    /// Checks the sizes of the proofs and of the multiproof of a mint against `MaxProofs`,
+    /// `MaxProofHashes` and `MaxProofFieldLength`, before any of them is hashed. No state is
+    /// read, so oversized mints are rejected cheaply.
+    pub fn check_proofs_limits(
+        proofs: &[Proof<H256>],
+        multi_proof: Option<&MultiProof<H256>>,
+    ) -> Result<(), proofs::ProofsError> {
+        let max_proofs = T::MaxProofs::get();
+        let max_hashes = T::MaxProofHashes::get();
+        let max_field_length = T::MaxProofFieldLength::get() as usize;
+
+        proofs::check_proofs_limits(proofs, max_proofs, max_hashes)?;
+        if let Some(index) = proofs.iter()
+            .position(|p| p.value.len() > max_field_length || p.property.len() > max_field_length) {
+            return Err(proofs::ProofsError::ProofFieldTooLong(index as u32));
+        }
+
+        match multi_proof {
+            Some(multi_proof) => proofs::check_multi_proof_limits(multi_proof, max_proofs, max_hashes),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the error of this module describing why proofs are not valid.
+    fn proofs_error(e: proofs::ProofsError) -> Error<T> {
+        match e {
+            proofs::ProofsError::AnchorNotFound => Error::<T>::DocumentNotAnchored,
+            proofs::ProofsError::EmptyProofs => Error::<T>::EmptyProofs,
+            proofs::ProofsError::UnknownDocumentLayout => Error::<T>::UnknownDocumentLayout,
+            proofs::ProofsError::StaticProofsMismatch => Error::<T>::StaticProofsMismatch,
+            proofs::ProofsError::DocumentRootMismatch => Error::<T>::DocumentRootMismatch,
+            proofs::ProofsError::InvalidProof(_) => Error::<T>::InvalidProof,
+            proofs::ProofsError::MalformedMultiProof => Error::<T>::MalformedMultiProof,
+            proofs::ProofsError::InvalidMultiProof => Error::<T>::InvalidMultiProof,
+            proofs::ProofsError::TooManyProofs => Error::<T>::TooManyProofs,
+            proofs::ProofsError::ProofTooLong(_) => Error::<T>::ProofTooLong,
+            proofs::ProofsError::ProofFieldTooLong(_) => Error::<T>::ProofFieldTooLong,
+            proofs::ProofsError::MultiProofTooLong => Error::<T>::MultiProofTooLong,
+        }
+    }
+
+    /// Adds the default hash function to the registries stored before registries had one.
+    fn migrate_registries() -> Weight {
+        if RegistriesVersion::get() >= REGISTRIES_VERSION {
+            return T::DbWeight::get().reads(1);
+        }
+
+        // Registries were encoded as (owner_can_burn, fields)
+        Registries::translate::<(bool, Vec<Bytes>), _>(|_, (owner_can_burn, fields)| {
+            Some(RegistryInfo { owner_can_burn, fields, hasher: Default::default() })
+        });
+        RegistriesVersion::put(REGISTRIES_VERSION);
+
+        // Every registry created so far has been read and written
+        let registries: u64 = RegistryNonce::get().saturated_into();
+        T::DbWeight::get().reads_writes(registries.saturating_add(2), registries.saturating_add(1))
+    }
+
     fn create_new_registry_id() -> Result<RegistryId, dispatch::DispatchError> {
         let id_nonce = <RegistryNonce>::get();
 
@@ -194,6 +409,13 @@ impl<T: Trait> VerifierRegistry for Module<T> {
             asset_info: T::AssetInfo,
             mint_info: MintInfo<<T as frame_system::Trait>::Hash, H256>,
     ) -> Result<(), dispatch::DispatchError> {
+        // Reject oversized proofs before reading any state
+        Self::check_proofs_limits(&mint_info.proofs, mint_info.multi_proof.as_ref())
+            .map_err(Self::proofs_error)?;
+        if let Some(evicted) = &mint_info.evicted_anchor {
+            <anchor::Module<T>>::check_evicted_anchor_proof_limits(&evicted.proof)?;
+        }
+
         let (registry_id, token_id) = AssetIdRef::from(asset_id).destruct();
         let registry_info = Registries::get(registry_id);
 
@@ -230,19 +452,34 @@ impl<T: Trait> VerifierRegistry for Module<T> {
         // -------------
         // Verify proofs
 
-        // Get the doc root
-        let doc_root = Self::get_document_root(mint_info.anchor_id)?;
+        // Get the doc root, either from a live anchor or from a proof of an evicted one
+        let doc_root = Self::get_document_root(mint_info.anchor_id, mint_info.evicted_anchor)?;
 
         // Generate leaf hashes, turn into proofs::Proof type for validation call
-        let proofs = mint_info.proofs.into_iter()
+        let proofs: Vec<proofs::Proof> = mint_info.proofs.into_iter()
             .map(|p| p.into())
             .collect();
 
-        // Verify the proof against document root
-        ensure!(proofs::validate_proofs(doc_root,
-                                        &proofs,
-                                        mint_info.static_hashes),
-                Error::<T>::InvalidProofs);
+        // Verify the proofs against document root, one by one or all at once
+        let layout = proofs::DocumentLayout::from_version(mint_info.document_layout)
+            .ok_or(Error::<T>::UnknownDocumentLayout)?;
+        let validation = match mint_info.multi_proof {
+            None => proofs::validate_proofs(doc_root,
+                                            &proofs,
+                                            &layout,
+                                            &mint_info.static_hashes,
+                                            registry_info.hasher),
+            Some(multi_proof) => {
+                let multi_proof = multi_proof.into_multi_proof(&proofs)
+                    .ok_or(Error::<T>::MalformedMultiProof)?;
+                proofs::validate_multi_proof(doc_root,
+                                             &multi_proof,
+                                             &layout,
+                                             &mint_info.static_hashes,
+                                             registry_info.hasher)
+            },
+        };
+        validation.map_err(Self::proofs_error)?;
 
         // -------
         // Minting
@@ -2,13 +2,19 @@ use crate::{proofs, anchor};
 use crate::nft::Error as NftError;
 use sp_runtime::traits::Hash;
 use sp_core::{H256, H160, U256, Encode};
-use frame_support::{assert_err, assert_ok};
+use frame_support::{
+    assert_err, assert_ok,
+    storage::{StorageMap, StorageValue},
+    traits::OnRuntimeUpgrade,
+    weights::GetDispatchInfo,
+};
 use crate::va_registry::{
     self, Error, mock::*,
     types::{AssetId, NFTS_PREFIX, Proof, TokenId, RegistryId,
-            MintInfo, RegistryInfo, AssetInfo, VerifierRegistry},
+            MintInfo, MultiProof, RegistryInfo, AssetInfo, VerifierRegistry},
 };
 use crate::nft;
+use hex_literal::hex;
 
 // Hash two hashes
 fn hash_of<T: frame_system::Trait>(a: H256, b: H256) -> T::Hash {
@@ -26,9 +32,10 @@ fn doc_root<T: frame_system::Trait>(static_hashes: [H256; 3]) -> T::Hash {
     hash_of::<T>(signing_root, signature_root)
 }
 
-// Some dummy proofs data useful for testing. Returns proofs, static hashes, and document root
-fn proofs_data<T: frame_system::Trait>(registry_id: RegistryId, token_id: TokenId)
-    -> (Vec<Proof<H256>>, [H256; 3], T::Hash) {
+// Some dummy proofs data useful for testing, with nodes hashed by the given hash function.
+// Returns proofs, static hashes, and document root
+fn proofs_data<T: frame_system::Trait>(registry_id: RegistryId, token_id: TokenId, hasher: proofs::HashFunction)
+    -> (Vec<Proof<H256>>, Vec<H256>, T::Hash) {
     // Encode token into big endian U256
     let mut token_enc = Vec::<u8>::with_capacity(32);
     unsafe { token_enc.set_len(32); }
@@ -39,7 +46,8 @@ fn proofs_data<T: frame_system::Trait>(registry_id: RegistryId, token_id: TokenI
         value: token_enc,
         salt: [1; 32],
         property: [NFTS_PREFIX, registry_id.as_bytes()].concat(),
-        hashes: vec![]};
+        hashes: vec![],
+        hasher: None};
 
     let proofs = vec![
         Proof {
@@ -47,6 +55,7 @@ fn proofs_data<T: frame_system::Trait>(registry_id: RegistryId, token_id: TokenI
             salt: [1; 32],
             property: b"AMOUNT".to_vec(),
             hashes: vec![proofs::Proof::from(pre_proof.clone()).leaf_hash],
+            hasher: None,
         },
         pre_proof.clone()
     ];
@@ -56,20 +65,34 @@ fn proofs_data<T: frame_system::Trait>(registry_id: RegistryId, token_id: TokenI
     let mut h: Vec<u8> = Vec::with_capacity(64);
     h.extend_from_slice(&leaves[0][..]);
     h.extend_from_slice(&leaves[1][..]);
-    let data_root     = sp_io::hashing::blake2_256(&h).into();
+    let data_root     = hasher.hash(&h);
     let zk_data_root  = sp_io::hashing::blake2_256(&[0]).into();
     let sig_root      = sp_io::hashing::blake2_256(&[0]).into();
     let static_hashes = [data_root, zk_data_root, sig_root];
     let doc_root      = doc_root::<T>(static_hashes);
 
-    (proofs, static_hashes, doc_root)
+    (proofs, static_hashes.to_vec(), doc_root)
 }
 
 // Creates a registry and returns all relevant data
 pub fn setup_mint<T>(owner: T::AccountId, token_id: TokenId)
     -> (AssetId,
         T::Hash, T::Hash,
-        (Vec<Proof<H256>>, [H256; 3], T::Hash),
+        (Vec<Proof<H256>>, Vec<H256>, T::Hash),
+        AssetInfo,
+        RegistryInfo)
+    where T: frame_system::Trait
+           + va_registry::Trait
+           + nft::Trait<AssetInfo = AssetInfo>,
+{
+    setup_mint_with_hasher::<T>(owner, token_id, Default::default())
+}
+
+// Creates a registry with the given hash function and returns all relevant data
+pub fn setup_mint_with_hasher<T>(owner: T::AccountId, token_id: TokenId, hasher: proofs::HashFunction)
+    -> (AssetId,
+        T::Hash, T::Hash,
+        (Vec<Proof<H256>>, Vec<H256>, T::Hash),
         AssetInfo,
         RegistryInfo)
     where T: frame_system::Trait
@@ -88,6 +111,7 @@ pub fn setup_mint<T>(owner: T::AccountId, token_id: TokenId)
         owner_can_burn: false,
         // Don't include the registry id prop which will be generated in the runtime
         fields: properties,
+        hasher,
     };
 
     // Create registry, get registry id. Shouldn't fail.
@@ -97,7 +121,7 @@ pub fn setup_mint<T>(owner: T::AccountId, token_id: TokenId)
     };
 
     // Proofs data
-    let (proofs, static_hashes, doc_root) = proofs_data::<T>(registry_id.clone(), token_id.clone());
+    let (proofs, static_hashes, doc_root) = proofs_data::<T>(registry_id.clone(), token_id.clone(), hasher);
 
     // Registry data
     let nft_data = AssetInfo {
@@ -149,7 +173,10 @@ fn mint_with_valid_proofs() {
                       MintInfo {
                           anchor_id: anchor_id,
                           proofs: proofs,
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1,
                           static_hashes: static_hashes,
+                          evicted_anchor: None,
                       }));
 
         // Nft registered to owner
@@ -160,6 +187,362 @@ fn mint_with_valid_proofs() {
     });
 }
 
+#[test]
+fn mint_with_registry_hasher() {
+    new_test_ext().execute_with(|| {
+        let token_id = U256::one();
+        let owner = 1;
+        let origin = Origin::signed(owner);
+        let (asset_id,
+             pre_image,
+             anchor_id,
+             (proofs, static_hashes, doc_root),
+             nft_data,
+             _) = setup_mint_with_hasher::<Test>(owner, token_id, proofs::HashFunction::Keccak256);
+
+        // Place document anchor into storage for verification
+        assert_ok!( <anchor::Module<Test>>::commit(
+            origin.clone(),
+            pre_image,
+            doc_root,
+            // Proof does not matter here
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            crate::common::MS_PER_DAY + 1) );
+
+        // Proofs are not valid with another hash function
+        let validation = SUT::validate_document_proofs(anchor_id,
+                                                       proofs::DOCUMENT_LAYOUT_V1,
+                                                       proofs::HashFunction::Blake2_256,
+                                                       static_hashes.clone(),
+                                                       proofs.clone());
+        assert_eq!(validation.error, Some(proofs::ProofsError::InvalidProof(0)));
+
+        // Unless each proof has its own
+        let keccak_proofs = proofs.iter().cloned()
+            .map(|p| Proof { hasher: Some(proofs::HashFunction::Keccak256), ..p })
+            .collect();
+        let validation = SUT::validate_document_proofs(anchor_id,
+                                                       proofs::DOCUMENT_LAYOUT_V1,
+                                                       proofs::HashFunction::Blake2_256,
+                                                       static_hashes.clone(),
+                                                       keccak_proofs);
+        assert_eq!(validation.error, None);
+
+        let (registry_id, token_id) = asset_id.destruct();
+
+        // Mint token with document proof hashed with the hash function of the registry
+        assert_ok!(
+            SUT::mint(origin,
+                      owner,
+                      registry_id,
+                      token_id,
+                      nft_data,
+                      MintInfo {
+                          anchor_id: anchor_id,
+                          proofs: proofs,
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1,
+                          static_hashes: static_hashes,
+                          evicted_anchor: None,
+                      }));
+    });
+}
+
+// Document fixture whose leaves are, in this order, an AMOUNT, a CURRENCY, a DUE_DATE and the
+// token id 1 of the first registry created. The leaves, the nodes of the data tree and the
+// document root were computed independently of this crate, with the Keccak 256 of OpenSSL and
+// the SHA2 256 and BLAKE2b 256 of the Python hashlib.
+//
+// Returns the registry id, the proofs of the AMOUNT and of the token id and the static hashes,
+// given the nodes of the AMOUNT and CURRENCY leaves and of the DUE_DATE and token leaves, and
+// the data root, which depend on the hash function.
+fn document_fixture(amount_currency_node: H256, due_date_token_node: H256, data_root: H256)
+    -> (RegistryId, Vec<Proof<H256>>, Vec<H256>) {
+    let registry_id = H160::from(hex!("94c1c088cc9453996779630ad3af45cbd9281482"));
+    let currency_leaf = H256::from(hex!("33c4637a8ea9c7f8def55501ef493122595292cb251461b9749bf64bd98b057e"));
+    let due_date_leaf = H256::from(hex!("37730a4a1ce27a66235942ce29e396a2462eb1374d6a89a58d99b2a4502522f2"));
+    let zk_data_root  = H256::from(hex!("00fb380682a9c9507f4ba4b00d188def05816442a15725b37ccbd20582554210"));
+    let sig_root      = H256::from(hex!("0ad50cf18c5ef7002c3f002a68e574b67ec32b5761dcec8ad8a382417c87c412"));
+
+    let proofs = vec![
+        Proof {
+            value: hex!("03e8").to_vec(),
+            salt: [0x11; 32],
+            property: b"AMOUNT".to_vec(),
+            hashes: vec![currency_leaf, due_date_token_node],
+            hasher: None,
+        },
+        Proof {
+            value: hex!("0000000000000000000000000000000000000000000000000000000000000001").to_vec(),
+            salt: [0x44; 32],
+            property: [NFTS_PREFIX, registry_id.as_bytes()].concat(),
+            hashes: vec![due_date_leaf, amount_currency_node],
+            hasher: None,
+        },
+    ];
+
+    (registry_id, proofs, vec![data_root, zk_data_root, sig_root])
+}
+
+// Validates the proofs of the document fixture, then mints the token with them in a registry
+// with the given hash function
+fn mint_document_fixture(hasher: proofs::HashFunction,
+                         amount_currency_node: H256,
+                         due_date_token_node: H256,
+                         data_root: H256,
+                         doc_root: H256) {
+    new_test_ext().execute_with(|| {
+        let owner = 1;
+        let origin = Origin::signed(owner);
+        let token_id = U256::one();
+        let (fixture_registry_id, proofs, static_hashes) =
+            document_fixture(amount_currency_node, due_date_token_node, data_root);
+
+        let registry_info = RegistryInfo {
+            owner_can_burn: false,
+            fields: vec![b"AMOUNT".to_vec()],
+            hasher,
+        };
+        let registry_id = <SUT as VerifierRegistry>::create_registry(owner, registry_info).unwrap();
+        assert_eq!(registry_id, fixture_registry_id);
+
+        // Proofs are valid against the document root of the fixture
+        let layout = proofs::DocumentLayout::from_version(proofs::DOCUMENT_LAYOUT_V1).unwrap();
+        let leaf_proofs: Vec<proofs::Proof> = proofs.iter().cloned().map(Into::into).collect();
+        assert_eq!(proofs::validate_proofs(doc_root, &leaf_proofs, &layout, &static_hashes, hasher), Ok(()));
+
+        // Place document anchor into storage for verification
+        let pre_image = <Test as frame_system::Trait>::Hashing::hash(&[1,2,3]);
+        let anchor_id = (pre_image).using_encoded(<Test as frame_system::Trait>::Hashing::hash);
+        assert_ok!( <anchor::Module<Test>>::commit(
+            origin.clone(),
+            pre_image,
+            doc_root,
+            // Proof does not matter here
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            crate::common::MS_PER_DAY + 1) );
+
+        assert_ok!(
+            SUT::mint(origin,
+                      owner,
+                      registry_id,
+                      token_id,
+                      AssetInfo { metadata: vec![] },
+                      MintInfo {
+                          anchor_id: anchor_id,
+                          proofs: proofs,
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1,
+                          static_hashes: static_hashes,
+                          evicted_anchor: None,
+                      }));
+
+        // Nft registered to owner
+        assert_eq!(
+            <nft::Module<Test>>::account_for_asset::<H160,U256>(registry_id, token_id),
+            Some(owner)
+        );
+    });
+}
+
+#[test]
+fn mint_with_keccak_document_fixture() {
+    mint_document_fixture(
+        proofs::HashFunction::Keccak256,
+        H256::from(hex!("18825631f8eae93ee5d3d0716f76ca54b3cc0c987218dc5ab08be8103feb31d3")),
+        H256::from(hex!("df50782c1908e356e1a2b9fbe2eb5f4f499eae1ba41c7be99ba64e57214e5ed3")),
+        H256::from(hex!("841cf08a15b3a7601f13ca16b0856ecd2113ead246d001864d5530f51abf4fd6")),
+        H256::from(hex!("a8b81d6bd124b3946f2a8dac0095d2512e84cc6598ed4548a3b2e9ba48fe3bc5")));
+}
+
+#[test]
+fn mint_with_sha2_document_fixture() {
+    mint_document_fixture(
+        proofs::HashFunction::Sha2_256,
+        H256::from(hex!("2b43ef886e0eab8d17c37a8a959d1c46c497ea0d8d7497c2d4b64386f22a48b7")),
+        H256::from(hex!("70da71e68b2b2ef1d39bd211ef04275691326b911f99cbebdfc974be49127c3d")),
+        H256::from(hex!("b5e8c024094a11bfe00b661fd291afc555d6ef27766004c8357028273ef0e8bd")),
+        H256::from(hex!("78b3f98760f7a2b96f3081715adb5b2483f80e2bac3fccef84eaf3691f94ba1b")));
+}
+
+#[test]
+fn migrate_registries() {
+    new_test_ext().execute_with(|| {
+        let registry_id = RegistryId::repeat_byte(1);
+        let fields = vec![b"AMOUNT".to_vec()];
+
+        // Registry stored before registries had a hash function
+        frame_support::storage::unhashed::put(
+            &va_registry::Registries::hashed_key_for(registry_id),
+            &(true, fields.clone()));
+
+        assert_eq!(va_registry::RegistriesVersion::get(), 0);
+        <SUT as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+        assert_eq!(va_registry::RegistriesVersion::get(), va_registry::REGISTRIES_VERSION);
+        assert_eq!(va_registry::Registries::get(registry_id), RegistryInfo {
+            owner_can_burn: true,
+            fields,
+            hasher: proofs::HashFunction::Blake2_256,
+        });
+    });
+}
+
+#[test]
+fn mint_with_multi_proof() {
+    new_test_ext().execute_with(|| {
+        let token_id = U256::one();
+        let owner = 1;
+        let origin = Origin::signed(owner);
+        let (asset_id,
+             pre_image,
+             anchor_id,
+             (proofs, static_hashes, doc_root),
+             nft_data,
+             _) = setup_mint::<Test>(owner, token_id);
+
+        // Place document anchor into storage for verification
+        assert_ok!( <anchor::Module<Test>>::commit(
+            origin.clone(),
+            pre_image,
+            doc_root,
+            // Proof does not matter here
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            crate::common::MS_PER_DAY + 1) );
+
+        let (registry_id, token_id) = asset_id.destruct();
+        let mint_info = |multi_proof: MultiProof<H256>| MintInfo {
+            anchor_id: anchor_id,
+            proofs: proofs.clone(),
+            multi_proof: Some(multi_proof),
+            document_layout: proofs::DOCUMENT_LAYOUT_V1,
+            static_hashes: static_hashes.clone(),
+            evicted_anchor: None,
+        };
+
+        // Every proof must be a leaf of the multiproof
+        assert_err!(
+            SUT::mint(origin.clone(),
+                      owner,
+                      registry_id,
+                      token_id,
+                      nft_data.clone(),
+                      mint_info(MultiProof { leaves: vec![1], hashes: vec![], flags: vec![] })),
+            Error::<Test>::MalformedMultiProof);
+
+        // The two leaves are the children of the data root
+        assert_err!(
+            SUT::mint(origin.clone(),
+                      owner,
+                      registry_id,
+                      token_id,
+                      nft_data.clone(),
+                      mint_info(MultiProof {
+                          leaves: vec![1, 0],
+                          hashes: vec![H256::zero()],
+                          flags: vec![true, false],
+                      })),
+            Error::<Test>::InvalidMultiProof);
+
+        assert_ok!(
+            SUT::mint(origin,
+                      owner,
+                      registry_id,
+                      token_id,
+                      nft_data,
+                      mint_info(MultiProof { leaves: vec![1, 0], hashes: vec![], flags: vec![true] })));
+
+        // Nft registered to owner
+        assert_eq!(
+            <nft::Module<Test>>::account_for_asset::<H160,U256>(registry_id, token_id),
+            Some(owner)
+        );
+    });
+}
+
+#[test]
+fn mint_with_evicted_anchor_proof() {
+    new_test_ext().execute_with(|| {
+        let token_id = U256::one();
+        let owner = 1;
+        let origin = Origin::signed(owner);
+        let (asset_id,
+             pre_image,
+             anchor_id,
+             (proofs, static_hashes, doc_root),
+             nft_data,
+             _) = setup_mint::<Test>(owner, token_id);
+
+        // Place document anchor into storage until day 2
+        assert_ok!( <anchor::Module<Test>>::commit(
+            origin.clone(),
+            pre_image,
+            doc_root,
+            // Proof does not matter here
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            crate::common::MS_PER_DAY + 1) );
+        let proof = anchor::tests::evicted_anchor_proof::<Test>(2, anchor_id);
+
+        // Evict the anchor
+        <pallet_timestamp::Module<Test>>::set_timestamp(crate::common::MS_PER_DAY * 3 + 1);
+        assert_ok!( <anchor::Module<Test>>::evict_anchors(origin.clone()) );
+
+        let (registry_id, token_id) = asset_id.destruct();
+
+        // Live anchor is gone
+        assert_err!(
+            SUT::mint(origin.clone(),
+                      owner,
+                      registry_id,
+                      token_id,
+                      nft_data.clone(),
+                      MintInfo {
+                          anchor_id: anchor_id,
+                          proofs: proofs.clone(),
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1,
+                          static_hashes: static_hashes.clone(),
+                          evicted_anchor: None,
+                      }),
+            Error::<Test>::DocumentNotAnchored);
+
+        // Oversized evicted anchor proofs are rejected
+        let mint_info = |proof| MintInfo {
+            anchor_id: anchor_id,
+            proofs: proofs.clone(),
+            multi_proof: None,
+            document_layout: proofs::DOCUMENT_LAYOUT_V1,
+            static_hashes: static_hashes.clone(),
+            evicted_anchor: Some(anchor::EvictedAnchorProof { day: 2, proof }),
+        };
+        let too_large = vec![vec![0; 1024]; 17];
+        assert_err!(
+            SUT::mint(origin.clone(), owner, registry_id, token_id, nft_data.clone(), mint_info(too_large.clone())),
+            anchor::Error::<Test>::EvictedAnchorProofTooLarge);
+
+        // The evicted anchor proof is weighed
+        let weight = |proof| va_registry::Call::<Test>::mint(owner, registry_id, token_id, nft_data.clone(), mint_info(proof))
+            .get_dispatch_info()
+            .weight;
+        assert!(weight(too_large) > weight(proof.clone()));
+
+        // Mint token with document proof and evicted anchor proof
+        assert_ok!(
+            SUT::mint(origin,
+                      owner,
+                      registry_id,
+                      token_id,
+                      nft_data,
+                      mint_info(proof)));
+
+        // Nft registered to owner
+        assert_eq!(
+            <nft::Module<Test>>::account_for_asset::<H160,U256>(registry_id, token_id),
+            Some(owner)
+        );
+    });
+}
+
 #[test]
 fn mint_fails_when_dont_match_doc_root() {
     new_test_ext().execute_with(|| {
@@ -195,9 +578,231 @@ fn mint_fails_when_dont_match_doc_root() {
                       MintInfo {
                           anchor_id: anchor_id,
                           proofs: proofs,
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1,
                           static_hashes: static_hashes,
+                          evicted_anchor: None,
                       }),
-            Error::<Test>::InvalidProofs);
+            Error::<Test>::DocumentRootMismatch);
+    });
+}
+
+#[test]
+fn mint_fails_with_invalid_proof() {
+    new_test_ext().execute_with(|| {
+        let token_id = U256::one();
+        let owner = 1;
+        let origin = Origin::signed(owner);
+        let (asset_id,
+             pre_image,
+             anchor_id,
+             (mut proofs, static_hashes, doc_root),
+             nft_data,
+             _) = setup_mint::<Test>(owner, token_id);
+
+        // Place document anchor into storage for verification
+        assert_ok!( <anchor::Module<Test>>::commit(
+            origin.clone(),
+            pre_image,
+            doc_root,
+            // Proof does not matter here
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            crate::common::MS_PER_DAY + 1) );
+
+        let (registry_id, token_id) = asset_id.destruct();
+
+        // The leaf of the first proof no longer leads to the document root
+        proofs[0].value = vec![2, 2];
+
+        // Mint token with document proof
+        assert_err!(
+            SUT::mint(origin,
+                      owner,
+                      registry_id,
+                      token_id,
+                      nft_data,
+                      MintInfo {
+                          anchor_id: anchor_id,
+                          proofs: proofs,
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1,
+                          static_hashes: static_hashes,
+                          evicted_anchor: None,
+                      }),
+            Error::<Test>::InvalidProof);
+    });
+}
+
+#[test]
+fn mint_fails_with_unknown_document_layout() {
+    new_test_ext().execute_with(|| {
+        let token_id = U256::one();
+        let owner = 1;
+        let origin = Origin::signed(owner);
+        let (asset_id,
+             pre_image,
+             anchor_id,
+             (proofs, static_hashes, doc_root),
+             nft_data,
+             _) = setup_mint::<Test>(owner, token_id);
+
+        // Place document anchor into storage for verification
+        assert_ok!( <anchor::Module<Test>>::commit(
+            origin.clone(),
+            pre_image,
+            doc_root,
+            // Proof does not matter here
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            crate::common::MS_PER_DAY + 1) );
+
+        let (registry_id, token_id) = asset_id.destruct();
+
+        // Mint token with a layout version that does not exist
+        assert_err!(
+            SUT::mint(origin.clone(),
+                      owner,
+                      registry_id,
+                      token_id,
+                      nft_data.clone(),
+                      MintInfo {
+                          anchor_id: anchor_id,
+                          proofs: proofs.clone(),
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1 + 1,
+                          static_hashes: static_hashes.clone(),
+                          evicted_anchor: None,
+                      }),
+            Error::<Test>::UnknownDocumentLayout);
+
+        // Mint token with static hashes that do not fit the layout
+        assert_err!(
+            SUT::mint(origin,
+                      owner,
+                      registry_id,
+                      token_id,
+                      nft_data,
+                      MintInfo {
+                          anchor_id: anchor_id,
+                          proofs: proofs,
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1,
+                          static_hashes: static_hashes[..2].to_vec(),
+                          evicted_anchor: None,
+                      }),
+            Error::<Test>::StaticProofsMismatch);
+    });
+}
+
+#[test]
+fn mint_fails_with_oversized_proofs() {
+    new_test_ext().execute_with(|| {
+        let token_id = U256::one();
+        let owner = 1;
+        let origin = Origin::signed(owner);
+        let (asset_id,
+             _,
+             anchor_id,
+             (proofs, static_hashes, _),
+             nft_data,
+             _) = setup_mint::<Test>(owner, token_id);
+        let (registry_id, token_id) = asset_id.destruct();
+        let mint_info = MintInfo {
+            anchor_id: anchor_id,
+            proofs: proofs.clone(),
+            multi_proof: None,
+            document_layout: proofs::DOCUMENT_LAYOUT_V1,
+            static_hashes: static_hashes.clone(),
+            evicted_anchor: None,
+        };
+
+        // The limits are checked before the document is looked up, so no anchor is needed
+        let mut too_many = mint_info.clone();
+        too_many.proofs = vec![proofs[0].clone(); 5];
+        assert_err!(
+            SUT::mint(origin.clone(), owner, registry_id, token_id, nft_data.clone(), too_many),
+            Error::<Test>::TooManyProofs);
+
+        let mut too_long = mint_info.clone();
+        too_long.proofs[1].hashes = vec![H256::zero(); 5];
+        assert_err!(
+            SUT::mint(origin.clone(), owner, registry_id, token_id, nft_data.clone(), too_long),
+            Error::<Test>::ProofTooLong);
+
+        let mut field_too_long = mint_info.clone();
+        field_too_long.proofs[0].value = vec![1; 65];
+        assert_err!(
+            SUT::mint(origin.clone(), owner, registry_id, token_id, nft_data.clone(), field_too_long),
+            Error::<Test>::ProofFieldTooLong);
+
+        // Multiproofs are weighed by their actual length, oversized ones included
+        let multi_proof_weight = |flags: usize| {
+            let mut mint_info = mint_info.clone();
+            mint_info.multi_proof = Some(MultiProof {
+                leaves: vec![0, 1],
+                hashes: vec![H256::zero(); flags - 1],
+                flags: vec![false; flags],
+            });
+            va_registry::Call::<Test>::mint(owner, registry_id, token_id, nft_data.clone(), mint_info)
+                .get_dispatch_info()
+                .weight
+        };
+        assert!(multi_proof_weight(100) > multi_proof_weight(10));
+
+        let mut multi_proof_too_long = mint_info.clone();
+        multi_proof_too_long.multi_proof = Some(MultiProof {
+            leaves: vec![0, 1],
+            hashes: vec![H256::zero(); 9],
+            flags: vec![false; 10],
+        });
+        assert_err!(
+            SUT::mint(origin, owner, registry_id, token_id, nft_data, multi_proof_too_long),
+            Error::<Test>::MultiProofTooLong);
+
+        // The proofs validation reports the first oversized proof
+        let mut proofs = proofs;
+        proofs[1].property = vec![1; 65];
+        let validation = SUT::validate_document_proofs(anchor_id, proofs::DOCUMENT_LAYOUT_V1, proofs::HashFunction::Blake2_256, static_hashes, proofs);
+        assert_eq!(validation.error, Some(proofs::ProofsError::ProofFieldTooLong(1)));
+    });
+}
+
+#[test]
+fn validate_document_proofs() {
+    new_test_ext().execute_with(|| {
+        let owner = 1;
+        let origin = Origin::signed(owner);
+        let (_,
+             pre_image,
+             anchor_id,
+             (proofs, static_hashes, doc_root),
+             _,
+             _) = setup_mint::<Test>(owner, U256::one());
+        let doc_root = H256::from_slice(doc_root.as_ref());
+
+        // Anchor has to exist
+        let validation = SUT::validate_document_proofs(anchor_id, proofs::DOCUMENT_LAYOUT_V1, proofs::HashFunction::Blake2_256, static_hashes.clone(), proofs.clone());
+        assert_eq!(validation.doc_root, doc_root);
+        assert_eq!(validation.valid_proofs, vec![true, true]);
+        assert_eq!(validation.error, Some(proofs::ProofsError::AnchorNotFound));
+
+        assert_ok!( <anchor::Module<Test>>::commit(
+            origin.clone(),
+            pre_image,
+            doc_root,
+            // Proof does not matter here
+            <Test as frame_system::Trait>::Hashing::hash_of(&0),
+            crate::common::MS_PER_DAY + 1) );
+
+        let validation = SUT::validate_document_proofs(anchor_id, proofs::DOCUMENT_LAYOUT_V1, proofs::HashFunction::Blake2_256, static_hashes.clone(), proofs.clone());
+        assert_eq!(validation.valid_proofs, vec![true, true]);
+        assert_eq!(validation.error, None);
+
+        // A proof with a wrong value is reported by its position
+        let mut invalid_proofs = proofs;
+        invalid_proofs[0].value = vec![2, 2];
+        let validation = SUT::validate_document_proofs(anchor_id, proofs::DOCUMENT_LAYOUT_V1, proofs::HashFunction::Blake2_256, static_hashes, invalid_proofs);
+        assert_eq!(validation.valid_proofs, vec![false, true]);
+        assert_eq!(validation.error, Some(proofs::ProofsError::InvalidProof(0)));
     });
 }
 
@@ -235,7 +840,10 @@ fn duplicate_mint_fails() {
                       MintInfo {
                           anchor_id: anchor_id,
                           proofs: proofs.clone(),
-                          static_hashes: static_hashes,
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1,
+                          static_hashes: static_hashes.clone(),
+                          evicted_anchor: None,
                       }));
 
         // Mint same token containing same id
@@ -248,7 +856,10 @@ fn duplicate_mint_fails() {
                       MintInfo {
                           anchor_id: anchor_id,
                           proofs: proofs,
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1,
                           static_hashes: static_hashes,
+                          evicted_anchor: None,
                       }),
             NftError::<Test>::AssetExists);
     });
@@ -289,7 +900,10 @@ fn mint_fails_with_wrong_tokenid_in_proof() {
                       MintInfo {
                           anchor_id: anchor_id,
                           proofs: proofs,
+                          multi_proof: None,
+                          document_layout: proofs::DOCUMENT_LAYOUT_V1,
                           static_hashes: static_hashes,
+                          evicted_anchor: None,
                       }),
             Error::<Test>::InvalidProofs);
     });
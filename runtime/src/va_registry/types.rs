@@ -1,5 +1,5 @@
 use sp_core::{U256, H160};
-use crate::{proofs, bridge};
+use crate::{anchor, proofs, bridge};
 use frame_support::dispatch;
 use codec::{Decode, Encode};
 use sp_std::{vec::Vec, fmt::Debug};
@@ -61,6 +61,9 @@ pub struct RegistryInfo {
     /// Names of fields required to be provided for verification during a [mint].
     /// These *MUST* be compact encoded.
     pub fields: Vec<Bytes>,
+    /// Hash function of the nodes of the document trees proven during a [mint], unless
+    /// a proof specifies its own.
+    pub hasher: proofs::HashFunction,
 }
 
 /// All data for an instance of an NFT.
@@ -73,6 +76,7 @@ pub struct AssetInfo {
 /// Proven by hashing hash(value + property + salt) into a leaf hash of the document
 /// merkle tree, then hashing with the given hashes to generate the merkle root.
 #[derive(Encode, Decode, Clone, PartialEq, Eq, Default, Debug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
 pub struct Proof<Hash> {
     /// The value of the associated property of a document. Corrseponds to a leaf in
     /// the document merkle tree.
@@ -83,9 +87,17 @@ pub struct Proof<Hash> {
     pub salt: Salt,
     /// A list of all extra hashes required to build the merkle root hash from the leaf.
     pub hashes: Vec<Hash>,
+    /// Hash function of the nodes of this proof, when it differs from the one of the registry.
+    pub hasher: Option<proofs::HashFunction>,
 }
 
 /// Generates the leaf hash from underlying data, other hashes remain the same.
+impl<Hash> proofs::ProofSize for Proof<Hash> {
+    fn hashes_len(&self) -> usize {
+        self.hashes.len()
+    }
+}
+
 impl From<Proof<sp_core::H256>> for proofs::Proof {
     fn from(mut p: Proof<sp_core::H256>) -> Self {
         // Generate leaf hash from property ++ value ++ salt
@@ -93,7 +105,62 @@ impl From<Proof<sp_core::H256>> for proofs::Proof {
         p.property.extend(&p.salt);
         let leaf_hash = sp_io::hashing::keccak_256(&p.property).into();
 
-        proofs::Proof::new(leaf_hash, p.hashes)
+        proofs::Proof::new(leaf_hash, p.hashes).with_hasher(p.hasher)
+    }
+}
+
+/// Hashes shared by all the proofs of a mint, which are then proven at once by a
+/// [proofs::MultiProof] instead of one by one.
+///
+/// The nodes shared by several proofs are given only once, so a mint with a multiproof is
+/// smaller than one with the hashes of each proof as soon as it has two proofs. A single proof
+/// is smaller without a multiproof, which adds a flag per node, the position of the leaf and the
+/// length prefixes, i.e. `d + 7` bytes for a proof of `d` hashes.
+///
+/// Each node is hashed once as well, and without looking it up in the proven nodes, so a mint
+/// with a multiproof is weighed by `WeightInfo::mint_multi_proof`, which is lower than
+/// `WeightInfo::mint` for the same number of proofs and depth.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Default, Debug)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct MultiProof<Hash> {
+    /// Position in the proofs of the mint of each leaf of the multiproof, in the order the
+    /// leaves are consumed. Every proof *MUST* appear exactly once.
+    pub leaves: Vec<u32>,
+    /// Nodes of the document tree which are not computed from the leaves.
+    pub hashes: Vec<Hash>,
+    /// See [proofs::MultiProof].
+    pub flags: Vec<bool>,
+}
+
+impl<Hash> proofs::MultiProofSize for MultiProof<Hash> {
+    fn leaves_len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    fn hashes_len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    fn flags_len(&self) -> usize {
+        self.flags.len()
+    }
+}
+
+impl MultiProof<sp_core::H256> {
+    /// Builds the multiproof of the given proofs, or returns `None` if the positions of the
+    /// leaves are not a permutation of the proofs.
+    pub fn into_multi_proof(self, proofs: &[proofs::Proof]) -> Option<proofs::MultiProof> {
+        let mut positions = self.leaves.clone();
+        positions.sort();
+        if !positions.into_iter().map(|p| p as usize).eq(0..proofs.len()) {
+            return None;
+        }
+
+        Some(proofs::MultiProof {
+            leaves: self.leaves.iter().map(|p| proofs[*p as usize].leaf_hash).collect(),
+            hashes: self.hashes,
+            flags: self.flags,
+        })
     }
 }
 
@@ -102,13 +169,23 @@ impl From<Proof<sp_core::H256>> for proofs::Proof {
 pub struct MintInfo<T, Hash> {
     /// Unique ID to an anchor document.
     pub anchor_id: T,
-    /// The three hashes [DataRoot, SignatureRoot, DocRoot] *MUST* be in this order.
-    /// These are used to validate the respective branches of the merkle tree, and
-    /// to generate the final document root hash.
-    pub static_hashes: [Hash; 3],
+    /// Version of the document tree layout, which defines how the static hashes
+    /// combine into the document root.
+    pub document_layout: proofs::DocumentLayoutVersion,
+    /// The static roots of the document, in the order of the document layout. For the
+    /// first layout version, the three hashes [BasicDataRoot, ZkDataRoot, SignatureRoot]
+    /// *MUST* be in this order. These are used to validate the respective branches of the
+    /// merkle tree, and to generate the final document root hash.
+    pub static_hashes: Vec<Hash>,
     /// Each element of the list is a proof that a certain property of a
     /// document has the specified value.
     pub proofs: Vec<Proof<Hash>>,
+    /// Hashes to prove all the proofs at once. The hashes of each proof are not used when
+    /// it is given.
+    pub multi_proof: Option<MultiProof<Hash>>,
+    /// Proof of the anchor if it has already been evicted from the anchor storage. The document
+    /// root is then taken from the evicted anchor instead of a live one.
+    pub evicted_anchor: Option<anchor::EvictedAnchorProof>,
 }
 
 /// An implementor of this trait *MUST* be an asset of a registry.
@@ -1,18 +1,98 @@
-use jsonrpc_core::Result;
+use codec::Decode;
+use futures::{future, FutureExt, StreamExt, TryFutureExt, TryStreamExt};
+use jsonrpc_core::{
+    futures::{Future, Sink},
+    BoxFuture, Result,
+};
 use jsonrpc_derive::rpc;
-use node_primitives::{BlockNumber, Hash};
-use node_runtime::anchor::AnchorData;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use log::warn;
+use node_primitives::{AccountId, Balance, BlockNumber, Hash, Moment};
+use node_runtime::anchor::{AnchorCounts, AnchorData, AnchorWithEvictDate};
 pub use node_runtime::AnchorApi as AnchorRuntimeApi;
-use sp_api::ProvideRuntimeApi;
+pub use node_runtime::FeesApi as FeesRuntimeApi;
+pub use node_runtime::ProofsApi as ProofsRuntimeApi;
+use node_runtime::{
+    fees::{FeeEstimate, FeeInfo, ScheduledFeeChange, Sponsorship},
+    proofs::{self, DocumentLayoutVersion, HashFunction, ProofsValidation},
+    va_registry::types::{Proof, RegistryId, TokenId},
+};
+use sc_client_api::{
+    light::{future_header, Fetcher, RemoteBlockchain, RemoteReadChildRequest, RemoteReadRequest},
+    BlockBackend, BlockchainEvents, ProofProvider,
+};
+use sc_rpc_api::state::ReadProof;
+use serde::{Deserialize, Serialize};
+use sp_api::{ApiErrorFor, ApiExt, ProvideRuntimeApi, RuntimeApiInfo};
 use sp_blockchain::HeaderBackend;
-use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_core::Bytes;
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block as BlockT, NumberFor},
+    DispatchError,
+};
 use std::sync::Arc;
 
+/// Error code of failed runtime API calls.
+const RUNTIME_ERROR: i64 = 1;
+/// Error code of anchors that do not exist at the queried block.
+const ANCHOR_NOT_FOUND: i64 = 2;
+/// Error code of fee estimations of calls that would fail.
+const FEE_ESTIMATION_FAILED: i64 = 3;
+/// Error code of queries at blocks whose runtime lacks the queried runtime API method.
+const API_UNSUPPORTED: i64 = 4;
+
+/// Version of the anchor runtime API that added all methods but `get_anchor_by_id`.
+const ANCHOR_API_VERSION: u32 = 2;
+/// Version of the proofs runtime API that added `validate_proofs`.
+const PROOFS_API_VERSION: u32 = 1;
+/// Version of the fees runtime API that added all its methods.
+const FEES_API_VERSION: u32 = 1;
+
 #[rpc]
 pub trait AnchorApi {
-    /// Returns an anchor given an anchor id from the runtime storage
+    /// Returns an anchor given an anchor id from the runtime storage. Queries the best block
+    /// unless `at` is given.
     #[rpc(name = "anchor_getAnchorById")]
-    fn get_anchor_by_id(&self, id: Hash) -> Result<AnchorData<Hash, BlockNumber>>;
+    fn get_anchor_by_id(&self, id: Hash, at: Option<Hash>)
+        -> Result<AnchorData<Hash, BlockNumber>>;
+
+    /// Returns an anchor given an anchor id from the runtime storage at the last finalized block.
+    #[rpc(name = "anchor_getFinalizedAnchorById")]
+    fn get_finalized_anchor_by_id(&self, id: Hash) -> Result<AnchorData<Hash, BlockNumber>>;
+
+    /// Returns a storage proof of an evicted anchor in the child trie of its eviction day. The
+    /// proof is read at the last block before the eviction and can be verified on chain against
+    /// the evicted child trie root.
+    #[rpc(name = "anchor_proveEvictedAnchor")]
+    fn prove_evicted_anchor(&self, id: Hash, day: u32) -> Result<ReadProof<Hash>>;
+
+    /// Returns up to `limit` anchors starting at anchor index `start`. Indexes of evicted anchors
+    /// are skipped. Queries the best block unless `at` is given.
+    #[rpc(name = "anchor_getAnchorsByIndexRange")]
+    fn get_anchors_by_index_range(
+        &self,
+        start: u64,
+        limit: u32,
+        at: Option<Hash>,
+    ) -> Result<Vec<AnchorWithEvictDate<Hash, BlockNumber>>>;
+
+    /// Returns up to `limit` anchors to be evicted on `day` in days since unix epoch, ordered by
+    /// anchor id and following the anchor id `start_after` if given. Queries the best block
+    /// unless `at` is given.
+    #[rpc(name = "anchor_getAnchorsByEvictDate")]
+    fn get_anchors_by_evict_date(
+        &self,
+        day: u32,
+        start_after: Option<Hash>,
+        limit: u32,
+        at: Option<Hash>,
+    ) -> Result<Vec<AnchorWithEvictDate<Hash, BlockNumber>>>;
+
+    /// Returns the number of committed anchors and the latest evicted anchor index. Queries the
+    /// best block unless `at` is given.
+    #[rpc(name = "anchor_getAnchorCounts")]
+    fn get_anchor_counts(&self, at: Option<Hash>) -> Result<AnchorCounts>;
 }
 
 /// A struct that implements the [`AnchorApi`].
@@ -31,23 +111,831 @@ impl<C, P> Anchor<C, P> {
     }
 }
 
+impl<C, Block> Anchor<C, Block>
+where
+    Block: BlockT<Hash = Hash>,
+    C: HeaderBackend<Block>,
+{
+    /// The block to query at, which is the best block unless `at` is given.
+    fn block_id(&self, at: Option<Hash>) -> BlockId<Block> {
+        BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+    }
+}
+
+impl<C, Block> Anchor<C, Block>
+where
+    Block: BlockT<Hash = Hash>,
+    C: ProvideRuntimeApi<Block>,
+    C::Api: AnchorRuntimeApi<Block>,
+{
+    /// Reads the anchor `id` at block `at`, failing if it does not exist there.
+    fn anchor_by_id_at(
+        &self,
+        id: Hash,
+        at: BlockId<Block>,
+    ) -> Result<AnchorData<Hash, BlockNumber>> {
+        self.client
+            .runtime_api()
+            .get_anchor_by_id(&at, id)
+            .map_err(|e| runtime_error("Unable to query anchor", e))?
+            .ok_or_else(|| anchor_not_found(id))
+    }
+}
+
 impl<C, Block> AnchorApi for Anchor<C, Block>
 where
-    Block: BlockT,
-    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    Block: BlockT<Hash = Hash>,
+    C: Send
+        + Sync
+        + 'static
+        + ProvideRuntimeApi<Block>
+        + HeaderBackend<Block>
+        + ProofProvider<Block>,
     C::Api: AnchorRuntimeApi<Block>,
 {
-    fn get_anchor_by_id(&self, id: Hash) -> Result<AnchorData<Hash, BlockNumber>> {
+    fn get_anchor_by_id(
+        &self,
+        id: Hash,
+        at: Option<Hash>,
+    ) -> Result<AnchorData<Hash, BlockNumber>> {
+        self.anchor_by_id_at(id, self.block_id(at))
+    }
+
+    fn get_finalized_anchor_by_id(&self, id: Hash) -> Result<AnchorData<Hash, BlockNumber>> {
+        self.anchor_by_id_at(id, BlockId::hash(self.client.info().finalized_hash))
+    }
+
+    fn prove_evicted_anchor(&self, id: Hash, day: u32) -> Result<ReadProof<Hash>> {
         let api = self.client.runtime_api();
-        let best = self.client.info().best_hash;
-        let at = BlockId::hash(best);
-        api.get_anchor_by_id(&at, id)
-            .ok()
-            .unwrap()
+        let best = BlockId::hash(self.client.info().best_hash);
+        ensure_anchor_api(&*self.client, &best)?;
+        let evicted_at = api
+            .get_evicted_anchor_block(&best, day)
+            .map_err(|e| runtime_error("Unable to query eviction block", e))?
+            .ok_or(jsonrpc_core::Error {
+                code: jsonrpc_core::ErrorCode::InvalidParams,
+                message: "Anchors of the given day have not been evicted".into(),
+                data: Some(format!("{:?}", day).into()),
+            })?;
+
+        // the child trie is removed in the eviction block, its parent still holds it
+        let at = self
+            .client
+            .hash(NumberFor::<Block>::from(evicted_at.saturating_sub(1)))
+            .map_err(|e| internal_error("Unable to find block before eviction", e))?
             .ok_or(jsonrpc_core::Error {
                 code: jsonrpc_core::ErrorCode::InternalError,
-                message: "Unable to find anchor".into(),
-                data: Some(format!("{:?}", id).into()),
-            })
+                message: "Block before eviction has been pruned".into(),
+                data: Some(format!("{:?}", evicted_at).into()),
+            })?;
+
+        let child_info = node_runtime::Anchor::anchor_child_info(day);
+        let proof = self
+            .client
+            .read_child_proof(
+                &BlockId::hash(at),
+                &child_info,
+                &mut std::iter::once(id.as_ref()),
+            )
+            .map_err(|e| internal_error("Unable to read anchor proof", e))?;
+
+        Ok(ReadProof {
+            at,
+            proof: proof.iter_nodes().map(Into::into).collect(),
+        })
+    }
+
+    fn get_anchors_by_index_range(
+        &self,
+        start: u64,
+        limit: u32,
+        at: Option<Hash>,
+    ) -> Result<Vec<AnchorWithEvictDate<Hash, BlockNumber>>> {
+        let at = self.block_id(at);
+        ensure_anchor_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .get_anchors_by_index_range(&at, start, limit)
+            .map_err(|e| runtime_error("Unable to list anchors", e))
+    }
+
+    fn get_anchors_by_evict_date(
+        &self,
+        day: u32,
+        start_after: Option<Hash>,
+        limit: u32,
+        at: Option<Hash>,
+    ) -> Result<Vec<AnchorWithEvictDate<Hash, BlockNumber>>> {
+        let at = self.block_id(at);
+        ensure_anchor_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .get_anchors_by_evict_date(&at, day, start_after, limit)
+            .map_err(|e| runtime_error("Unable to list anchors", e))
+    }
+
+    fn get_anchor_counts(&self, at: Option<Hash>) -> Result<AnchorCounts> {
+        let at = self.block_id(at);
+        ensure_anchor_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .get_anchor_counts(&at)
+            .map_err(|e| runtime_error("Unable to count anchors", e))
+    }
+}
+
+#[rpc]
+pub trait LightAnchorApi {
+    /// Returns an anchor given an anchor id from the runtime storage. Queries the best block
+    /// unless `at` is given.
+    #[rpc(name = "anchor_getAnchorById")]
+    fn get_anchor_by_id(
+        &self,
+        id: Hash,
+        at: Option<Hash>,
+    ) -> BoxFuture<AnchorData<Hash, BlockNumber>>;
+
+    /// Returns an anchor given an anchor id from the runtime storage at the last finalized block.
+    #[rpc(name = "anchor_getFinalizedAnchorById")]
+    fn get_finalized_anchor_by_id(&self, id: Hash) -> BoxFuture<AnchorData<Hash, BlockNumber>>;
+}
+
+/// A struct that implements the [`LightAnchorApi`]. Anchors are read from full peers through the
+/// fetcher, which checks the storage proofs of the reads against the state root of the queried
+/// block.
+pub struct LightAnchor<C, F, Block: BlockT> {
+    client: Arc<C>,
+    remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+    fetcher: Arc<F>,
+}
+
+impl<C, F, Block: BlockT> LightAnchor<C, F, Block> {
+    /// Create new `LightAnchor` with the given reference to the client, the remote blockchain
+    /// and the fetcher.
+    pub fn new(
+        client: Arc<C>,
+        remote_blockchain: Arc<dyn RemoteBlockchain<Block>>,
+        fetcher: Arc<F>,
+    ) -> Self {
+        LightAnchor {
+            client,
+            remote_blockchain,
+            fetcher,
+        }
+    }
+}
+
+impl<C, F, Block> LightAnchor<C, F, Block>
+where
+    Block: BlockT<Hash = Hash>,
+    F: Fetcher<Block> + 'static,
+{
+    /// Reads the anchor `id` at block `at` from full peers. The eviction date of the anchor is
+    /// read first, as it determines the child trie that holds the anchor.
+    fn anchor_by_id_at(&self, id: Hash, at: Hash) -> BoxFuture<AnchorData<Hash, BlockNumber>> {
+        let remote_blockchain = self.remote_blockchain.clone();
+        let fetcher = self.fetcher.clone();
+        let anchor = async move {
+            let header = future_header(&*remote_blockchain, &*fetcher, BlockId::hash(at))
+                .await
+                .map_err(|e| internal_error("Unable to fetch block header", e))?
+                .ok_or_else(|| internal_error("Unable to find block", at))?;
+
+            let evict_date_key = node_runtime::Anchor::anchor_evict_date_key(id);
+            let evict_date = fetcher
+                .remote_read(RemoteReadRequest {
+                    block: at,
+                    header: header.clone(),
+                    keys: vec![evict_date_key.clone()],
+                    retry_count: None,
+                })
+                .await
+                .map_err(|e| internal_error("Unable to read anchor eviction date", e))?
+                .remove(&evict_date_key)
+                .flatten()
+                .map(|data| u32::decode(&mut &*data))
+                .transpose()
+                .map_err(|e| internal_error("Unable to decode anchor eviction date", e))?
+                // same as on chain, anchors without an eviction date are looked up on day 0
+                .unwrap_or_default();
+
+            let child_info = node_runtime::Anchor::anchor_child_info(evict_date);
+            let data = fetcher
+                .remote_read_child(RemoteReadChildRequest {
+                    block: at,
+                    header,
+                    storage_key: child_info.prefixed_storage_key(),
+                    keys: vec![id.as_ref().to_vec()],
+                    retry_count: None,
+                })
+                .await
+                .map_err(|e| internal_error("Unable to read anchor", e))?
+                .remove(id.as_ref())
+                .flatten()
+                .ok_or_else(|| anchor_not_found(id))?;
+
+            AnchorData::decode(&mut &*data)
+                .map_err(|e| internal_error("Unable to decode anchor", e))
+        };
+
+        Box::new(anchor.boxed().compat())
+    }
+}
+
+impl<C, F, Block> LightAnchorApi for LightAnchor<C, F, Block>
+where
+    Block: BlockT<Hash = Hash>,
+    C: Send + Sync + 'static + HeaderBackend<Block>,
+    F: Fetcher<Block> + 'static,
+{
+    fn get_anchor_by_id(
+        &self,
+        id: Hash,
+        at: Option<Hash>,
+    ) -> BoxFuture<AnchorData<Hash, BlockNumber>> {
+        self.anchor_by_id_at(id, at.unwrap_or_else(|| self.client.info().best_hash))
+    }
+
+    fn get_finalized_anchor_by_id(&self, id: Hash) -> BoxFuture<AnchorData<Hash, BlockNumber>> {
+        self.anchor_by_id_at(id, self.client.info().finalized_hash)
+    }
+}
+
+#[rpc]
+pub trait ProofsApi {
+    /// Validates the proofs of the document anchored as `anchor_id` the same way as a mint of the
+    /// verifiable attributes registry does, without submitting a transaction. Returns the
+    /// validity of each proof, the document root computed from the static proofs and the reason
+    /// why the proofs are not valid. The static proofs are combined as described by the
+    /// `document_layout` version and the proofs are hashed with the `hasher` of the registry,
+    /// unless a proof has its own. Queries the best block unless `at` is given.
+    #[rpc(name = "proofs_validate")]
+    fn validate(
+        &self,
+        anchor_id: Hash,
+        document_layout: DocumentLayoutVersion,
+        hasher: HashFunction,
+        static_proofs: Vec<Hash>,
+        proofs: Vec<Proof<Hash>>,
+        at: Option<Hash>,
+    ) -> Result<ProofsValidation>;
+}
+
+/// A struct that implements the [`ProofsApi`].
+pub struct Proofs<C, P> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<P>,
+}
+
+impl<C, P> Proofs<C, P> {
+    /// Create new `Proofs` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        Proofs {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<C, Block> ProofsApi for Proofs<C, Block>
+where
+    Block: BlockT<Hash = Hash>,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: ProofsRuntimeApi<Block>,
+{
+    fn validate(
+        &self,
+        anchor_id: Hash,
+        document_layout: DocumentLayoutVersion,
+        hasher: HashFunction,
+        static_proofs: Vec<Hash>,
+        proofs: Vec<Proof<Hash>>,
+        at: Option<Hash>,
+    ) -> Result<ProofsValidation> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        ensure_proofs_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .validate_proofs(
+                &at,
+                anchor_id,
+                document_layout,
+                hasher,
+                static_proofs,
+                proofs,
+            )
+            .map_err(|e| runtime_error("Unable to validate proofs", e))
+    }
+}
+
+#[rpc]
+pub trait FeesApi {
+    /// Returns the registered fee keys with their name, unit, owning pallet, current price and
+    /// distribution. Queries the best block unless `at` is given.
+    #[rpc(name = "fees_list")]
+    fn list(&self, at: Option<Hash>) -> Result<Vec<FeeInfo<Hash, Balance>>>;
+
+    /// Returns the fee changes scheduled after the queried block, by effective block. Queries the
+    /// best block unless `at` is given.
+    #[rpc(name = "fees_scheduledChanges")]
+    fn scheduled_changes(
+        &self,
+        at: Option<Hash>,
+    ) -> Result<Vec<ScheduledFeeChange<Hash, Balance, BlockNumber>>>;
+
+    /// Estimates the cost of committing an anchor stored until `stored_until_date` with
+    /// `anchor::commit`: the weight and length fees of the transaction and the state rent.
+    /// Queries the best block unless `at` is given.
+    #[rpc(name = "fees_estimateCommit")]
+    fn estimate_commit(
+        &self,
+        stored_until_date: Moment,
+        at: Option<Hash>,
+    ) -> Result<FeeEstimate<Balance>>;
+
+    /// Estimates the cost of minting an NFT with `nfts::validate_mint` and the given arguments:
+    /// the weight and length fees of the transaction and the NFT mint fee. Queries the best
+    /// block unless `at` is given.
+    #[rpc(name = "fees_estimateNftMint")]
+    fn estimate_nft_mint(
+        &self,
+        anchor_id: Hash,
+        deposit_address: [u8; 20],
+        proofs: Vec<proofs::Proof>,
+        document_layout: DocumentLayoutVersion,
+        static_proofs: Vec<Hash>,
+        dest_id: u8,
+        at: Option<Hash>,
+    ) -> Result<FeeEstimate<Balance>>;
+
+    /// Estimates the cost of transferring `amount` native tokens through the bridge with
+    /// `bridge::transfer_native`: the weight and length fees of the transaction and the token
+    /// transfer fee. Queries the best block unless `at` is given.
+    #[rpc(name = "fees_estimateNativeTransfer")]
+    fn estimate_native_transfer(
+        &self,
+        amount: Balance,
+        recipient: Bytes,
+        dest_id: u8,
+        at: Option<Hash>,
+    ) -> Result<FeeEstimate<Balance>>;
+
+    /// Estimates the cost of transferring an NFT through the bridge with
+    /// `bridge::transfer_asset`: the weight and length fees of the transaction and the NFT
+    /// transfer fee. Queries the best block unless `at` is given.
+    #[rpc(name = "fees_estimateNftTransfer")]
+    fn estimate_nft_transfer(
+        &self,
+        recipient: Bytes,
+        from_registry: RegistryId,
+        token_id: TokenId,
+        dest_id: u8,
+        at: Option<Hash>,
+    ) -> Result<FeeEstimate<Balance>>;
+
+    /// Returns the allowance covering the fees of `account`, with its sponsor, remaining amount
+    /// and expiry, if any. Queries the best block unless `at` is given.
+    #[rpc(name = "fees_sponsorship")]
+    fn sponsorship(
+        &self,
+        account: AccountId,
+        at: Option<Hash>,
+    ) -> Result<Option<Sponsorship<AccountId, Balance, BlockNumber>>>;
+}
+
+/// A struct that implements the [`FeesApi`].
+pub struct Fees<C, P> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<P>,
+}
+
+impl<C, P> Fees<C, P> {
+    /// Create new `Fees` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        Fees {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<C, Block> FeesApi for Fees<C, Block>
+where
+    Block: BlockT<Hash = Hash>,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: FeesRuntimeApi<Block>,
+{
+    fn list(&self, at: Option<Hash>) -> Result<Vec<FeeInfo<Hash, Balance>>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        ensure_fees_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .fees(&at)
+            .map_err(|e| runtime_error("Unable to query fees", e))
+    }
+
+    fn scheduled_changes(
+        &self,
+        at: Option<Hash>,
+    ) -> Result<Vec<ScheduledFeeChange<Hash, Balance, BlockNumber>>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        ensure_fees_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .scheduled_fee_changes(&at)
+            .map_err(|e| runtime_error("Unable to query scheduled fee changes", e))
+    }
+
+    fn estimate_commit(
+        &self,
+        stored_until_date: Moment,
+        at: Option<Hash>,
+    ) -> Result<FeeEstimate<Balance>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        ensure_fees_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .estimate_commit_fee(&at, stored_until_date)
+            .map_err(|e| runtime_error("Unable to estimate fees", e))?
+            .map_err(fee_estimation_failed)
+    }
+
+    fn estimate_nft_mint(
+        &self,
+        anchor_id: Hash,
+        deposit_address: [u8; 20],
+        proofs: Vec<proofs::Proof>,
+        document_layout: DocumentLayoutVersion,
+        static_proofs: Vec<Hash>,
+        dest_id: u8,
+        at: Option<Hash>,
+    ) -> Result<FeeEstimate<Balance>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        ensure_fees_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .estimate_nft_mint_fee(
+                &at,
+                anchor_id,
+                deposit_address,
+                proofs,
+                document_layout,
+                static_proofs,
+                dest_id,
+            )
+            .map_err(|e| runtime_error("Unable to estimate fees", e))?
+            .map_err(fee_estimation_failed)
+    }
+
+    fn estimate_native_transfer(
+        &self,
+        amount: Balance,
+        recipient: Bytes,
+        dest_id: u8,
+        at: Option<Hash>,
+    ) -> Result<FeeEstimate<Balance>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        ensure_fees_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .estimate_native_transfer_fee(&at, amount, recipient.to_vec(), dest_id)
+            .map_err(|e| runtime_error("Unable to estimate fees", e))
+    }
+
+    fn estimate_nft_transfer(
+        &self,
+        recipient: Bytes,
+        from_registry: RegistryId,
+        token_id: TokenId,
+        dest_id: u8,
+        at: Option<Hash>,
+    ) -> Result<FeeEstimate<Balance>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        ensure_fees_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .estimate_nft_transfer_fee(&at, recipient.to_vec(), from_registry, token_id, dest_id)
+            .map_err(|e| runtime_error("Unable to estimate fees", e))?
+            .map_err(fee_estimation_failed)
+    }
+
+    fn sponsorship(
+        &self,
+        account: AccountId,
+        at: Option<Hash>,
+    ) -> Result<Option<Sponsorship<AccountId, Balance, BlockNumber>>> {
+        let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+        ensure_fees_api(&*self.client, &at)?;
+        self.client
+            .runtime_api()
+            .sponsorship(&at, account)
+            .map_err(|e| runtime_error("Unable to query sponsorship", e))
+    }
+}
+
+/// Filter of anchor subscriptions. An anchor is notified about if it matches all given criteria.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AnchorFilter {
+    /// Only anchors with one of these ids.
+    pub anchor_ids: Option<Vec<Hash>>,
+    /// Only anchors committed by this account.
+    pub account: Option<AccountId>,
+}
+
+impl AnchorFilter {
+    fn matches(&self, anchor_id: &Hash, who: &AccountId) -> bool {
+        self.anchor_ids
+            .as_ref()
+            .map_or(true, |anchor_ids| anchor_ids.contains(anchor_id))
+            && self.account.as_ref().map_or(true, |account| account == who)
+    }
+}
+
+#[rpc]
+pub trait AnchorSubscriptionApi {
+    /// RPC Metadata
+    type Metadata;
+
+    /// Notifies about anchors committed in new best blocks, optionally filtered by anchor ids or
+    /// by the committing account.
+    #[pubsub(
+        subscription = "anchor_anchors",
+        subscribe,
+        name = "anchor_subscribeAnchors"
+    )]
+    fn subscribe_anchors(
+        &self,
+        metadata: Self::Metadata,
+        subscriber: Subscriber<AnchorData<Hash, BlockNumber>>,
+        filter: Option<AnchorFilter>,
+    );
+
+    /// Unsubscribe from notifications about anchors committed in new best blocks.
+    #[pubsub(
+        subscription = "anchor_anchors",
+        unsubscribe,
+        name = "anchor_unsubscribeAnchors"
+    )]
+    fn unsubscribe_anchors(
+        &self,
+        metadata: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool>;
+
+    /// Notifies about anchors committed in finalized blocks, optionally filtered by anchor ids or
+    /// by the committing account.
+    #[pubsub(
+        subscription = "anchor_finalizedAnchors",
+        subscribe,
+        name = "anchor_subscribeFinalizedAnchors"
+    )]
+    fn subscribe_finalized_anchors(
+        &self,
+        metadata: Self::Metadata,
+        subscriber: Subscriber<AnchorData<Hash, BlockNumber>>,
+        filter: Option<AnchorFilter>,
+    );
+
+    /// Unsubscribe from notifications about anchors committed in finalized blocks.
+    #[pubsub(
+        subscription = "anchor_finalizedAnchors",
+        unsubscribe,
+        name = "anchor_unsubscribeFinalizedAnchors"
+    )]
+    fn unsubscribe_finalized_anchors(
+        &self,
+        metadata: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool>;
+}
+
+/// A struct that implements the [`AnchorSubscriptionApi`].
+pub struct AnchorSubscriptions<C, P> {
+    client: Arc<C>,
+    manager: SubscriptionManager,
+    _marker: std::marker::PhantomData<P>,
+}
+
+impl<C, P> AnchorSubscriptions<C, P> {
+    /// Create new `AnchorSubscriptions` with the given reference to the client and the manager
+    /// of the pubsub subscribers.
+    pub fn new(client: Arc<C>, manager: SubscriptionManager) -> Self {
+        AnchorSubscriptions {
+            client,
+            manager,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<C, Block> AnchorSubscriptions<C, Block>
+where
+    Block: BlockT<Hash = Hash>,
+    C: Send + Sync + 'static + ProvideRuntimeApi<Block> + BlockBackend<Block>,
+    C::Api: AnchorRuntimeApi<Block>,
+{
+    /// Notifies `subscriber` about the anchors committed in each of the `blocks` that match the
+    /// `filter`.
+    fn subscribe(
+        &self,
+        subscriber: Subscriber<AnchorData<Hash, BlockNumber>>,
+        blocks: impl futures::Stream<Item = Hash> + Send + Unpin + 'static,
+        filter: Option<AnchorFilter>,
+    ) {
+        let client = self.client.clone();
+        let filter = filter.unwrap_or_default();
+        self.manager.add(subscriber, move |sink| {
+            let anchors = blocks
+                .map(move |hash| {
+                    let at = BlockId::hash(hash);
+                    let anchors = ensure_anchor_api(&*client, &at)
+                        .and_then(|_| {
+                            client
+                                .block_body(&at)
+                                .map_err(|e| runtime_error("Unable to query block body", e))
+                        })
+                        .and_then(|extrinsics| {
+                            client
+                                .runtime_api()
+                                .get_committed_anchors(&at, extrinsics.unwrap_or_default())
+                                .map_err(|e| runtime_error("Unable to query anchors", e))
+                        })
+                        .unwrap_or_else(|e| {
+                            warn!("Unable to query anchors committed in {}: {:?}", hash, e);
+                            Vec::new()
+                        })
+                        .into_iter()
+                        .filter(|(anchor_id, who, _)| filter.matches(anchor_id, who))
+                        .map(|(_, _, anchor)| Ok::<_, ()>(Ok(anchor)))
+                        .collect::<Vec<_>>();
+                    futures::stream::iter(anchors)
+                })
+                .flatten()
+                .compat();
+
+            sink.sink_map_err(|e| warn!("Error sending anchor notifications: {:?}", e))
+                .send_all(anchors)
+                // the subscription ends with the stream of blocks
+                .map(|_| ())
+        });
+    }
+}
+
+impl<C, Block> AnchorSubscriptionApi for AnchorSubscriptions<C, Block>
+where
+    Block: BlockT<Hash = Hash>,
+    C: Send
+        + Sync
+        + 'static
+        + ProvideRuntimeApi<Block>
+        + BlockBackend<Block>
+        + BlockchainEvents<Block>,
+    C::Api: AnchorRuntimeApi<Block>,
+{
+    type Metadata = sc_rpc_api::Metadata;
+
+    fn subscribe_anchors(
+        &self,
+        _metadata: Self::Metadata,
+        subscriber: Subscriber<AnchorData<Hash, BlockNumber>>,
+        filter: Option<AnchorFilter>,
+    ) {
+        let blocks = self
+            .client
+            .import_notification_stream()
+            .filter_map(|notification| {
+                future::ready(Some(notification.hash).filter(|_| notification.is_new_best))
+            });
+        self.subscribe(subscriber, blocks, filter)
+    }
+
+    fn unsubscribe_anchors(
+        &self,
+        _metadata: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        Ok(self.manager.cancel(id))
+    }
+
+    fn subscribe_finalized_anchors(
+        &self,
+        _metadata: Self::Metadata,
+        subscriber: Subscriber<AnchorData<Hash, BlockNumber>>,
+        filter: Option<AnchorFilter>,
+    ) {
+        let blocks = self
+            .client
+            .finality_notification_stream()
+            .map(|notification| notification.hash);
+        self.subscribe(subscriber, blocks, filter)
+    }
+
+    fn unsubscribe_finalized_anchors(
+        &self,
+        _metadata: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        Ok(self.manager.cancel(id))
+    }
+}
+
+/// Fails with an error if the runtime at block `at` lacks the anchor runtime API methods added
+/// in `ANCHOR_API_VERSION`.
+fn ensure_anchor_api<C, Block>(client: &C, at: &BlockId<Block>) -> Result<()>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block>,
+    C::Api: AnchorRuntimeApi<Block>,
+{
+    ensure_api::<dyn AnchorRuntimeApi<Block, Error = ApiErrorFor<C, Block>>, _, _>(
+        client,
+        at,
+        ANCHOR_API_VERSION,
+    )
+}
+
+/// Fails with an error if the runtime at block `at` lacks the proofs runtime API methods added
+/// in `PROOFS_API_VERSION`.
+fn ensure_proofs_api<C, Block>(client: &C, at: &BlockId<Block>) -> Result<()>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block>,
+    C::Api: ProofsRuntimeApi<Block>,
+{
+    ensure_api::<dyn ProofsRuntimeApi<Block, Error = ApiErrorFor<C, Block>>, _, _>(
+        client,
+        at,
+        PROOFS_API_VERSION,
+    )
+}
+
+/// Fails with an error if the runtime at block `at` lacks the fees runtime API methods added in
+/// `FEES_API_VERSION`.
+fn ensure_fees_api<C, Block>(client: &C, at: &BlockId<Block>) -> Result<()>
+where
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block>,
+    C::Api: FeesRuntimeApi<Block>,
+{
+    ensure_api::<dyn FeesRuntimeApi<Block, Error = ApiErrorFor<C, Block>>, _, _>(
+        client,
+        at,
+        FEES_API_VERSION,
+    )
+}
+
+/// Fails with an error if the runtime at block `at` lacks the runtime API `Api`, or has a version
+/// of it older than `version`.
+fn ensure_api<Api, C, Block>(client: &C, at: &BlockId<Block>, version: u32) -> Result<()>
+where
+    Api: RuntimeApiInfo + ?Sized,
+    Block: BlockT,
+    C: ProvideRuntimeApi<Block>,
+{
+    let supported = client
+        .runtime_api()
+        .has_api_with::<Api, _>(at, |v| v >= version)
+        .map_err(|e| runtime_error("Unable to query runtime API version", e))?;
+    if supported {
+        Ok(())
+    } else {
+        Err(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(API_UNSUPPORTED),
+            message: "Runtime API method not supported at the queried block".into(),
+            data: Some(format!("{:?}", at).into()),
+        })
+    }
+}
+
+fn runtime_error(message: &str, e: impl std::fmt::Debug) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(RUNTIME_ERROR),
+        message: message.into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+fn fee_estimation_failed(e: DispatchError) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(FEE_ESTIMATION_FAILED),
+        message: "The estimated call would fail".into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+fn anchor_not_found(id: Hash) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(ANCHOR_NOT_FOUND),
+        message: "Unable to find anchor".into(),
+        data: Some(format!("{:?}", id).into()),
+    }
+}
+
+fn internal_error(message: &str, e: impl std::fmt::Debug) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::InternalError,
+        message: message.into(),
+        data: Some(format!("{:?}", e).into()),
     }
 }
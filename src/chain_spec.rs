@@ -268,6 +268,7 @@ pub fn testnet_genesis(
             )],
         }),
 		pallet_vesting: Some(Default::default()),
+		va_registry: Some(Default::default()),
     }
 }
 
@@ -47,8 +47,13 @@ use sp_blockchain::{Error as BlockChainError, HeaderMetadata, HeaderBackend};
 use sp_consensus::SelectChain;
 use sp_consensus_babe::BabeApi;
 use sp_transaction_pool::TransactionPool;
-use crate::api::{AnchorApi, Anchor};
+use crate::api::{
+    AnchorApi, Anchor, AnchorSubscriptionApi, AnchorSubscriptions, LightAnchorApi, LightAnchor,
+    ProofsApi, Proofs, FeesApi, Fees,
+};
 pub use node_runtime::AnchorApi as AnchorRuntimeApi;
+pub use node_runtime::ProofsApi as ProofsRuntimeApi;
+pub use node_runtime::FeesApi as FeesRuntimeApi;
 
 /// Light client extra dependencies.
 pub struct LightDeps<C, F, P> {
@@ -109,12 +114,17 @@ pub fn create_full<C, P, SC>(
 ) -> jsonrpc_core::IoHandler<sc_rpc_api::Metadata> where
     C: ProvideRuntimeApi<Block>,
     C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
+    C: sc_client_api::ProofProvider<Block>,
+    C: sc_client_api::BlockchainEvents<Block>,
+    C: sc_client_api::BlockBackend<Block>,
     C: Send + Sync + 'static,
     C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
     C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
     C::Api: BabeApi<Block>,
     C::Api: BlockBuilder<Block>,
     C::Api: AnchorRuntimeApi<Block>,
+    C::Api: ProofsRuntimeApi<Block>,
+    C::Api: FeesRuntimeApi<Block>,
     P: TransactionPool + 'static,
     SC: SelectChain<Block> +'static,
 {
@@ -167,7 +177,7 @@ pub fn create_full<C, P, SC>(
                 shared_authority_set,
                 shared_voter_state,
                 justification_stream,
-                subscriptions,
+                subscriptions.clone(),
             )
         )
     );
@@ -175,6 +185,15 @@ pub fn create_full<C, P, SC>(
     io.extend_with(
         AnchorApi::to_delegate(Anchor::new(client.clone()))
     );
+    io.extend_with(
+        AnchorSubscriptionApi::to_delegate(AnchorSubscriptions::new(client.clone(), subscriptions))
+    );
+    io.extend_with(
+        ProofsApi::to_delegate(Proofs::new(client.clone()))
+    );
+    io.extend_with(
+        FeesApi::to_delegate(Fees::new(client.clone()))
+    );
 
     io
 }
@@ -199,7 +218,12 @@ pub fn create_light<C, P, M, F>(
     } = deps;
     let mut io = jsonrpc_core::IoHandler::default();
     io.extend_with(
-        SystemApi::<Hash, AccountId, Index>::to_delegate(LightSystem::new(client, remote_blockchain, fetcher, pool))
+        SystemApi::<Hash, AccountId, Index>::to_delegate(
+            LightSystem::new(client.clone(), remote_blockchain.clone(), fetcher.clone(), pool)
+        )
+    );
+    io.extend_with(
+        LightAnchorApi::to_delegate(LightAnchor::new(client, remote_blockchain, fetcher))
     );
 
     io